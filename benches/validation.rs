@@ -0,0 +1,20 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use nhi::{is_nhi, NHI};
+
+const INPUTS: [&str; 8] = [
+    "ZAC5361", "zbn77vl", "JBX3656", "ZZZ0044", "ZZZ00AA", "ZBN77", "IBN77VL", "ZBÑ77VL",
+];
+
+fn validation(c: &mut Criterion) {
+    c.bench_function("is_nhi", |b| {
+        b.iter(|| INPUTS.iter().filter(|s| is_nhi(black_box(s))).count())
+    });
+    c.bench_function("from_str", |b| {
+        b.iter(|| INPUTS.iter().filter_map(|s| black_box(s).parse::<NHI>().ok()).count())
+    });
+}
+
+criterion_group!(benches, validation);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+/*
+ * C interface to the nhi crate, enabled with its `ffi` feature.
+ *
+ * Strings must be null-terminated and UTF-8 encoded. Null pointers and invalid UTF-8 are
+ * treated as invalid NHIs.
+ */
+
+#ifndef NHI_H
+#define NHI_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Format codes returned by nhi_format */
+#define NHI_FORMAT_INVALID -1
+#define NHI_FORMAT_OLD 0
+#define NHI_FORMAT_NEW 1
+
+/* Returns true if the string is a valid NHI, and false otherwise */
+bool nhi_is_valid(const char *nhi);
+
+/* Returns NHI_FORMAT_OLD or NHI_FORMAT_NEW for a valid NHI, and NHI_FORMAT_INVALID otherwise */
+int nhi_format(const char *nhi);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
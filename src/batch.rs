@@ -0,0 +1,428 @@
+//! Helpers for validating many NHI numbers at once.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, BufRead};
+
+use crate::{normalize_with_log, NhiFormat, ParseNhiError, NHI};
+
+/// Splits a string holding many potential NHIs and validates each of them
+///
+/// Tokens are separated by any run of ASCII whitespace (spaces, tabs, newlines), commas (`,`)
+/// or semicolons (`;`), so empty tokens between consecutive delimiters are ignored. This suits
+/// text areas where users paste lists of NHIs.
+///
+/// returns: the valid NHIs and the raw invalid tokens, each in the order they appeared
+///
+/// # Examples
+///
+/// ```
+/// use nhi::parse_list;
+///
+/// let (valid, invalid) = parse_list("ZAC5361, zbn77vl\nZZZ0044;;JBX3656");
+/// assert_eq!(valid.len(), 3);
+/// assert_eq!(invalid, vec!["ZZZ0044"]);
+/// ```
+pub fn parse_list(s: &str) -> (Vec<NHI>, Vec<String>) {
+    let mut valid = Vec::new();
+    let mut invalid = Vec::new();
+    for token in s.split(|c: char| c.is_ascii_whitespace() || c == ',' || c == ';') {
+        if token.is_empty() {
+            continue;
+        }
+        match token.parse() {
+            Ok(nhi) => valid.push(nhi),
+            Err(_) => invalid.push(token.to_string()),
+        }
+    }
+    (valid, invalid)
+}
+
+/// Validates many potential NHIs in a single pass, splitting them by format
+///
+/// This shows at a glance how far a dataset has migrated to the new format. Each bucket keeps
+/// the order in which its values were first seen, and duplicates are kept.
+///
+/// returns: the valid old format NHIs, the valid new format NHIs and the raw invalid strings
+///
+/// # Examples
+///
+/// ```
+/// use nhi::bucket_by_format;
+///
+/// let (old, new, invalid) = bucket_by_format(["ZAC5361", "zbn77vl", "ZZZ0044", "JBX3656"]);
+/// assert_eq!(old.len(), 2);
+/// assert_eq!(new[0].as_str(), "ZBN77VL");
+/// assert_eq!(invalid, vec!["ZZZ0044"]);
+/// ```
+pub fn bucket_by_format<'a, I: IntoIterator<Item = &'a str>>(
+    iter: I,
+) -> (Vec<NHI>, Vec<NHI>, Vec<String>) {
+    let mut old = Vec::new();
+    let mut new = Vec::new();
+    let mut invalid = Vec::new();
+    for s in iter {
+        match s.parse::<NHI>() {
+            Ok(nhi) if nhi.format() == NhiFormat::New => new.push(nhi),
+            Ok(nhi) => old.push(nhi),
+            Err(_) => invalid.push(s.to_string()),
+        }
+    }
+    (old, new, invalid)
+}
+
+/// Validates many potential NHIs, returning the position, value and reason for each invalid one
+///
+/// Use [partition_nhis] to also collect the valid NHIs.
+///
+/// # Arguments
+///
+/// * `iter`: the potential NHI strings, e.g. a column of a CSV upload
+///
+/// returns: the index, original value and [ParseNhiError] of each invalid item, in ascending
+/// order of index
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{validate_all, ParseNhiError};
+///
+/// let invalid = validate_all(["ZAC5361", "ZZZ0044", "zbn77vl", "ZBN77"]);
+/// assert_eq!(invalid, vec![
+///     (1, "ZZZ0044", ParseNhiError::NoValidCheckDigit),
+///     (3, "ZBN77", ParseNhiError::InvalidLength),
+/// ]);
+/// ```
+pub fn validate_all<I: IntoIterator<Item = S>, S: AsRef<str>>(
+    iter: I,
+) -> Vec<(usize, S, ParseNhiError)> {
+    iter.into_iter()
+        .enumerate()
+        .filter_map(|(i, s)| match s.as_ref().parse::<NHI>() {
+            Ok(_) => None,
+            Err(error) => Some((i, s, error)),
+        })
+        .collect()
+}
+
+/// Validates many potential NHIs, separating the valid NHIs from the position, value and reason
+/// of each invalid one
+///
+/// # Arguments
+///
+/// * `iter`: the potential NHI strings, e.g. a column of a CSV upload
+///
+/// returns: the valid NHIs in the order they appeared, and the index, original value and
+/// [ParseNhiError] of each invalid item, in ascending order of index
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{partition_nhis, ParseNhiError};
+///
+/// let (valid, invalid) = partition_nhis(["ZAC5361", "ZZZ0044", "zbn77vl"]);
+/// assert_eq!(valid.len(), 2);
+/// assert_eq!(invalid, vec![(1, "ZZZ0044".to_string(), ParseNhiError::NoValidCheckDigit)]);
+/// ```
+pub fn partition_nhis<I: IntoIterator<Item = S>, S: AsRef<str>>(
+    iter: I,
+) -> (Vec<NHI>, Vec<(usize, String, ParseNhiError)>) {
+    let mut valid = Vec::new();
+    let mut invalid = Vec::new();
+    for (i, s) in iter.into_iter().enumerate() {
+        match s.as_ref().parse() {
+            Ok(nhi) => valid.push(nhi),
+            Err(error) => invalid.push((i, s.as_ref().to_string(), error)),
+        }
+    }
+    (valid, invalid)
+}
+
+impl NHI {
+    /// Validates many potential NHIs from inconsistently cased sources, collecting the valid
+    /// NHIs into a deduplicated, ordered set
+    ///
+    /// Parsing normalizes case, so e.g. `"zbn77vl"` and `"ZBN77VL"` collapse to a single entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `iter`: the potential NHI strings
+    ///
+    /// returns: the set of unique valid NHIs, and the original value and [ParseNhiError] of
+    /// each invalid item in the order they appeared
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{ParseNhiError, NHI};
+    ///
+    /// let (unique, invalid) = NHI::collect_unique(["zbn77vl", "ZBN77VL", "ZZZ0044", "ZAC5361"]);
+    /// assert_eq!(unique.len(), 2);
+    /// assert_eq!(invalid, vec![("ZZZ0044".to_string(), ParseNhiError::NoValidCheckDigit)]);
+    /// ```
+    pub fn collect_unique<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        iter: I,
+    ) -> (BTreeSet<NHI>, Vec<(String, ParseNhiError)>) {
+        let mut unique = BTreeSet::new();
+        let mut invalid = Vec::new();
+        for s in iter {
+            match s.as_ref().parse() {
+                Ok(nhi) => {
+                    unique.insert(nhi);
+                }
+                Err(error) => invalid.push((s.as_ref().to_string(), error)),
+            }
+        }
+        (unique, invalid)
+    }
+}
+
+/// Lazily validates each line of a newline-delimited stream of potential NHIs, e.g. a dump too
+/// large to load into memory. Requires the `std` feature.
+///
+/// Lines are split on `\n` and trailing whitespace (including a `\r`) is trimmed before each
+/// line is validated as by [NHI::from_bytes]. Blank lines are skipped rather than reported,
+/// but still count towards the line indices.
+///
+/// # Arguments
+///
+/// * `r`: the stream of potential NHIs, one per line
+///
+/// returns: an iterator over the zero-based index and validation result of each non-blank line,
+/// or the error of a failed read
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use nhi::{validate_reader, ParseNhiError};
+///
+/// let dump = Cursor::new("ZAC5361\r\n\nZZZ0044\n");
+/// let results: Vec<_> = validate_reader(dump).map(Result::unwrap).collect();
+/// assert_eq!(results[0].0, 0);
+/// assert_eq!(results[0].1.as_ref().unwrap().as_str(), "ZAC5361");
+/// assert_eq!(results[1], (2, Err(ParseNhiError::NoValidCheckDigit)));
+/// ```
+#[cfg(feature = "std")]
+pub fn validate_reader<R: BufRead>(
+    r: R,
+) -> impl Iterator<Item = io::Result<(usize, Result<NHI, ParseNhiError>)>> {
+    r.split(b'\n').enumerate().filter_map(|(i, line)| match line {
+        Ok(line) if line.trim_ascii_end().is_empty() => None,
+        Ok(line) => Some(Ok((i, NHI::from_bytes(line.trim_ascii_end())))),
+        Err(error) => Some(Err(error)),
+    })
+}
+
+/// Normalizes and validates a user-entered string, returning the index of the first matching
+/// NHI in the given list
+///
+/// The string is normalized with [normalize_with_log] and compared to the list in its
+/// canonical form, so `" zbn-77-vl "` matches `ZBN77VL`.
+///
+/// returns: the index of the first matching NHI, or `None` if the string is not a valid NHI or
+/// is not in the list. The two cases are not distinguished, parse the string first if that is
+/// needed.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{position_in, NHI};
+///
+/// let roster: Vec<NHI> = ["ZAC5361", "ZBN77VL"].iter().map(|s| s.parse().unwrap()).collect();
+/// assert_eq!(position_in(" zbn-77-vl ", &roster), Some(1));
+/// assert_eq!(position_in("JBX3656", &roster), None);
+/// assert_eq!(position_in("ZZZ0044", &roster), None);
+/// ```
+pub fn position_in(s: &str, list: &[NHI]) -> Option<usize> {
+    let nhi: NHI = normalize_with_log(s).0.parse().ok()?;
+    list.iter().position(|item| *item == nhi)
+}
+
+/// Finds the valid NHIs that appear more than once among the given items
+///
+/// Items are validated case-insensitively, so `"ZAC5361"` and `"zac5361"` are duplicates.
+/// Invalid items are ignored for duplicate detection, use [crate::invalid_indices] to report them
+/// separately. Values are grouped by their packed encoding (see [NHI::to_packed]) rather than as
+/// strings.
+///
+/// returns: each duplicated NHI with the ascending indices at which it appears, ordered by the
+/// index of its first appearance
+///
+/// # Examples
+///
+/// ```
+/// use nhi::find_duplicates;
+///
+/// let duplicates = find_duplicates(&["ZAC5361", "ZBN77VL", "ZZZ0044", "zac5361", "ZZZ0044"]);
+/// assert_eq!(duplicates.len(), 1);
+/// assert_eq!(duplicates[0].0.as_str(), "ZAC5361");
+/// assert_eq!(duplicates[0].1, vec![0, 3]);
+/// ```
+pub fn find_duplicates(items: &[&str]) -> Vec<(NHI, Vec<usize>)> {
+    let mut groups: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    let mut order = Vec::new();
+    for (i, item) in items.iter().enumerate() {
+        if let Ok(nhi) = item.parse::<NHI>() {
+            let indices = groups.entry(nhi.to_packed()).or_default();
+            if indices.is_empty() {
+                order.push(nhi);
+            }
+            indices.push(i);
+        }
+    }
+    order
+        .into_iter()
+        .filter_map(|nhi| {
+            let indices = groups.remove(&nhi.to_packed()).unwrap();
+            (indices.len() > 1).then_some((nhi, indices))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CharacterClass;
+
+    #[test]
+    fn lists_are_split_on_all_delimiters() {
+        let (valid, invalid) = parse_list(" ZAC5361,,zbn77vl \t\r\nZZZ0044 ; JBX3656;ZBN-77-VL,\n");
+        let valid: Vec<&str> = valid.iter().map(NHI::as_str).collect();
+        assert_eq!(valid, vec!["ZAC5361", "ZBN77VL", "JBX3656"]);
+        assert_eq!(invalid, vec!["ZZZ0044", "ZBN-77-VL"]);
+    }
+
+    #[test]
+    fn empty_lists_yield_nothing() {
+        for s in ["", " ", ",;\n\t"] {
+            let (valid, invalid) = parse_list(s);
+            assert!(valid.is_empty());
+            assert!(invalid.is_empty());
+        }
+    }
+
+    #[test]
+    fn nhis_are_bucketed_by_format_in_first_seen_order() {
+        let items = ["ZBN77VL", "ZZZ0044", "zac5361", "XYZ12AN", "", "JBX3656", "ZBN77VL"];
+        let (old, new, invalid) = bucket_by_format(items);
+        let old: Vec<&str> = old.iter().map(NHI::as_str).collect();
+        let new: Vec<&str> = new.iter().map(NHI::as_str).collect();
+        assert_eq!(old, vec!["ZAC5361", "JBX3656"]);
+        assert_eq!(new, vec!["ZBN77VL", "XYZ12AN", "ZBN77VL"]);
+        assert_eq!(invalid, vec!["ZZZ0044", ""]);
+        let (old, new, invalid) = bucket_by_format(Vec::new());
+        assert!(old.is_empty() && new.is_empty() && invalid.is_empty());
+    }
+
+    #[test]
+    fn invalid_items_are_reported_with_their_index_and_reason() {
+        let items = ["ZAC5361", "ZZZ0044", "zbn77vl", "ZBN77VK", "", "IBN77VL", "JBX3656", "ZBN77V!"];
+        let expected = vec![
+            (1, "ZZZ0044", ParseNhiError::NoValidCheckDigit),
+            (3, "ZBN77VK", ParseNhiError::ChecksumMismatch { expected: 'L', found: 'K' }),
+            (4, "", ParseNhiError::InvalidLength),
+            (5, "IBN77VL", ParseNhiError::ForbiddenLetter { letter: 'I', index: 0 }),
+            (7, "ZBN77V!", ParseNhiError::UnexpectedCharacter {
+                index: 6,
+                expected: CharacterClass::Letter,
+            }),
+        ];
+        assert_eq!(validate_all(items), expected);
+        let owned: Vec<String> = items.iter().map(|s| s.to_string()).collect();
+        let invalid = validate_all(&owned);
+        assert_eq!(invalid.len(), expected.len());
+        for ((i, s, error), (expected_i, expected_s, expected_error)) in invalid.iter().zip(&expected) {
+            assert_eq!((i, s.as_str(), error), (expected_i, *expected_s, expected_error));
+        }
+
+        let (valid, invalid) = partition_nhis(owned);
+        let valid: Vec<&str> = valid.iter().map(NHI::as_str).collect();
+        assert_eq!(valid, vec!["ZAC5361", "ZBN77VL", "JBX3656"]);
+        let expected: Vec<_> = expected.into_iter().map(|(i, s, error)| (i, s.to_string(), error)).collect();
+        assert_eq!(invalid, expected);
+        assert_eq!(partition_nhis(Vec::<&str>::new()), (vec![], vec![]));
+    }
+
+    #[test]
+    fn mixed_case_duplicates_are_collected_once() {
+        let items = ["zbn77vl", "ZAC5361", "ZBN77VL", "", "JBX3656", "zBn77Vl", "ZZZ0044", "zac5361"];
+        let (unique, invalid) = NHI::collect_unique(items);
+        let unique: Vec<&str> = unique.iter().map(NHI::as_str).collect();
+        assert_eq!(unique, vec!["JBX3656", "ZAC5361", "ZBN77VL"]);
+        assert_eq!(invalid, vec![
+            (String::new(), ParseNhiError::InvalidLength),
+            (String::from("ZZZ0044"), ParseNhiError::NoValidCheckDigit),
+        ]);
+        assert_eq!(NHI::collect_unique(Vec::<String>::new()), (BTreeSet::new(), vec![]));
+    }
+
+    #[test]
+    fn positions_are_found_after_normalization() {
+        let list: Vec<NHI> = ["ZAC5361", "ZBN77VL", "ZAC5361"].iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(position_in("ZAC5361", &list), Some(0));
+        assert_eq!(position_in("zbn 77 vl", &list), Some(1));
+        assert_eq!(position_in("JBX3656", &list), None);
+        assert_eq!(position_in("ZZZ0044", &list), None);
+        assert_eq!(position_in("", &list), None);
+        assert_eq!(position_in("ZAC5361", &[]), None);
+    }
+
+    #[test]
+    fn duplicates_are_grouped_in_order_of_first_appearance() {
+        let items = ["ZBN77VL", "ZAC5361", "zac5361", "JBX3656", "ZBN77VL", "ZAC5361", "ZBN 77VL"];
+        let duplicates: Vec<(String, Vec<usize>)> = find_duplicates(&items)
+            .into_iter()
+            .map(|(nhi, indices)| (nhi.into_string(), indices))
+            .collect();
+        assert_eq!(duplicates, vec![
+            (String::from("ZBN77VL"), vec![0, 4]),
+            (String::from("ZAC5361"), vec![1, 2, 5]),
+        ]);
+    }
+
+    #[test]
+    fn invalid_and_unique_items_are_not_duplicates() {
+        assert!(find_duplicates(&[]).is_empty());
+        assert!(find_duplicates(&["ZAC5361", "ZBN77VL", "JBX3656"]).is_empty());
+        assert!(find_duplicates(&["ZZZ0044", "ZZZ0044", "", ""]).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn readers_are_validated_line_by_line() {
+        let dump = b"ZAC5361\nzbn77vl  \r\n\n   \r\nZZZ0044\nZBN77\nZBN77V\xFF\nJBX3656";
+        let results: Vec<_> = validate_reader(io::Cursor::new(dump))
+            .map(|result| result.unwrap())
+            .map(|(i, result)| (i, result.map(NHI::into_string)))
+            .collect();
+        assert_eq!(results, vec![
+            (0, Ok("ZAC5361".to_string())),
+            (1, Ok("ZBN77VL".to_string())),
+            (4, Err(ParseNhiError::NoValidCheckDigit)),
+            (5, Err(ParseNhiError::InvalidLength)),
+            (6, Err(ParseNhiError::NonAscii { index: 6 })),
+            (7, Ok("JBX3656".to_string())),
+        ]);
+        assert_eq!(validate_reader(io::Cursor::new("\n\r\n")).count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn read_errors_are_yielded() {
+        struct Failing;
+
+        impl io::Read for Failing {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("disconnected"))
+            }
+        }
+
+        let mut results = validate_reader(io::BufReader::new(Failing));
+        assert_eq!(results.next().unwrap().unwrap_err().to_string(), "disconnected");
+    }
+}
@@ -0,0 +1,82 @@
+//! Parsing NHI numbers from command line arguments with `clap`.
+//!
+//! Requires the `clap` feature.
+
+use std::ffi::OsStr;
+
+use clap::builder::{TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
+use clap::{Arg, Command, Error};
+
+use crate::NHI;
+
+/// A `clap` value parser that validates and parses arguments to [NHI]s with their
+/// [FromStr](std::str::FromStr) implementation
+///
+/// This is the parser `clap::value_parser!(NHI)` selects, so `NHI`-typed `#[arg]` fields of
+/// derived parsers need no explicit `value_parser`. Invalid arguments are reported with the
+/// reason they are not valid NHIs.
+///
+/// # Examples
+///
+/// ```
+/// use clap::{Arg, Command};
+/// use nhi::{NhiValueParser, NHI};
+///
+/// let command = Command::new("admit").arg(Arg::new("nhi").value_parser(NhiValueParser));
+/// let matches = command.clone().try_get_matches_from(["admit", "zbn77vl"]).unwrap();
+/// assert_eq!(matches.get_one::<NHI>("nhi").unwrap().as_str(), "ZBN77VL");
+/// assert!(command.try_get_matches_from(["admit", "ZBN77VK"]).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NhiValueParser;
+
+impl TypedValueParser for NhiValueParser {
+    type Value = NHI;
+
+    fn parse_ref(&self, cmd: &Command, arg: Option<&Arg>, value: &OsStr) -> Result<NHI, Error> {
+        let arg = arg.map_or_else(|| "...".to_string(), |arg| arg.to_string());
+        let value = value.to_string_lossy();
+        value.parse().map_err(|error| {
+            let message = format!("invalid value '{value}' for '{arg}': {error}\n");
+            Error::raw(ErrorKind::ValueValidation, message).with_cmd(cmd)
+        })
+    }
+}
+
+impl ValueParserFactory for NHI {
+    type Parser = NhiValueParser;
+
+    fn value_parser() -> NhiValueParser {
+        NhiValueParser
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{INVALID_NEW, INVALID_OLD, VALID_NEW, VALID_OLD};
+
+    fn command() -> Command {
+        Command::new("admit").arg(Arg::new("nhi").required(true).value_parser(clap::value_parser!(NHI)))
+    }
+
+    #[test]
+    fn valid_arguments_are_parsed() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let matches = command().try_get_matches_from(["admit", &nhi_str.to_lowercase()]).unwrap();
+            assert_eq!(matches.get_one::<NHI>("nhi").unwrap().as_str(), *nhi_str);
+        }
+    }
+
+    #[test]
+    fn invalid_arguments_are_reported() {
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(["ZBN77", "ZBN-77-VL"].iter()) {
+            let error = command().try_get_matches_from(["admit", nhi_str]).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::ValueValidation);
+            assert_ne!(error.exit_code(), 0);
+            let reason = nhi_str.parse::<NHI>().unwrap_err().to_string();
+            assert!(error.to_string().contains(&format!("'{nhi_str}' for '<nhi>': {reason}")));
+        }
+    }
+}
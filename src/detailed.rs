@@ -0,0 +1,146 @@
+//! A detailed serialization of NHI numbers, carrying their format and test status.
+//!
+//! Requires the `serde` feature.
+
+use alloc::string::String;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{NhiFormat, NHI};
+
+/// An [NHI] that serializes as an object holding its value, format and test status rather than
+/// as a plain string, so clients need not recompute them
+///
+/// The format is serialized as `"old"` or `"new"`. When deserializing, the value is validated
+/// as for [NHI] and the `format` and `is_test` fields may be omitted, but if present they must
+/// agree with the value.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "json")]
+/// # fn main() {
+/// use nhi::{NhiDetailed, NHI};
+///
+/// let nhi: NHI = "ZBN77VL".parse().unwrap();
+/// let json = serde_json::to_string(&NhiDetailed(nhi.clone())).unwrap();
+/// assert_eq!(json, r#"{"value":"ZBN77VL","format":"new","is_test":true}"#);
+///
+/// let detailed: NhiDetailed = serde_json::from_str(r#"{"value":"zbn77vl"}"#).unwrap();
+/// assert_eq!(detailed.0, nhi);
+/// # }
+/// # #[cfg(not(feature = "json"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct NhiDetailed(pub NHI);
+
+impl From<NHI> for NhiDetailed {
+    fn from(nhi: NHI) -> Self {
+        NhiDetailed(nhi)
+    }
+}
+
+impl From<NhiDetailed> for NHI {
+    fn from(detailed: NhiDetailed) -> Self {
+        detailed.0
+    }
+}
+
+#[derive(Serialize)]
+struct Fields<'a> {
+    value: &'a str,
+    format: &'static str,
+    is_test: bool,
+}
+
+#[derive(Deserialize)]
+struct OptionalFields {
+    value: NHI,
+    format: Option<String>,
+    is_test: Option<bool>,
+}
+
+/// Returns the serialized name of a format
+fn format_name(format: NhiFormat) -> &'static str {
+    match format {
+        NhiFormat::Old => "old",
+        NhiFormat::New => "new",
+    }
+}
+
+impl Serialize for NhiDetailed {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Fields {
+            value: self.0.as_str(),
+            format: format_name(self.0.format()),
+            is_test: self.0.is_test(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for NhiDetailed {
+    /// Deserializes an object holding a valid NHI, failing if its `format` or `is_test` fields
+    /// are present and do not agree with its value
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = OptionalFields::deserialize(deserializer)?;
+        let nhi = fields.value;
+        if fields.format.is_some_and(|format| format != format_name(nhi.format())) {
+            return Err(D::Error::custom("format does not match the NHI value"));
+        }
+        if fields.is_test.is_some_and(|is_test| is_test != nhi.is_test()) {
+            return Err(D::Error::custom("is_test does not match the NHI value"));
+        }
+        Ok(NhiDetailed(nhi))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "json")]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn plain_and_detailed_forms_round_trip() {
+        for (nhi_str, format) in VALID_OLD.iter().map(|s| (s, "old"))
+            .chain(VALID_NEW.iter().map(|s| (s, "new")))
+        {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let plain = serde_json::to_value(&nhi).unwrap();
+            assert_eq!(plain, json!(nhi_str));
+            assert_eq!(serde_json::from_value::<NHI>(plain).unwrap(), nhi);
+
+            let detailed = serde_json::to_value(NhiDetailed(nhi.clone())).unwrap();
+            assert_eq!(detailed, json!({
+                "value": nhi_str,
+                "format": format,
+                "is_test": nhi_str.starts_with('Z'),
+            }));
+            assert_eq!(serde_json::from_value::<NhiDetailed>(detailed).unwrap().0, nhi);
+        }
+    }
+
+    #[test]
+    fn detailed_values_are_validated() {
+        let parse = |value| serde_json::from_value::<NhiDetailed>(value);
+        assert_eq!(parse(json!({"value": "jbx3656"})).unwrap().0.as_str(), "JBX3656");
+        assert!(parse(json!({"value": "JBX3657"})).is_err());
+        assert!(parse(json!({"format": "old", "is_test": false})).is_err());
+        assert!(parse(json!("JBX3656")).is_err());
+    }
+
+    #[test]
+    fn detailed_fields_must_agree_with_the_value() {
+        let parse = |value| serde_json::from_value::<NhiDetailed>(value);
+        assert!(parse(json!({"value": "JBX3656", "format": "old", "is_test": false})).is_ok());
+        assert!(parse(json!({"value": "JBX3656", "format": "new"})).is_err());
+        assert!(parse(json!({"value": "JBX3656", "format": "Old"})).is_err());
+        assert!(parse(json!({"value": "JBX3656", "is_test": true})).is_err());
+        assert!(parse(json!({"value": "ZBN77VL", "is_test": false})).is_err());
+    }
+}
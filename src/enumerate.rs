@@ -0,0 +1,426 @@
+//! Enumeration of the valid NHI numbers sharing a three-letter prefix.
+//!
+//! Within a prefix, valid NHIs are enumerated old format first, then new format, each in
+//! ascending order. This order is part of the crate's stable API.
+
+use alloc::format;
+use alloc::string::String;
+#[cfg(feature = "rand")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "rand")]
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{checksum, expected_check_char, NhiFormat, LETTERS, NHI};
+
+/// The number of new format NHIs sharing a prefix (100 digit pairs × 24 letters), all of which
+/// have a check character
+const NEW_PER_PREFIX: u32 = 100 * 24;
+
+/// The number of legal three-letter prefixes
+const PREFIXES: u32 = 24 * 24 * 24;
+
+impl NHI {
+    /// Returns the position of this NHI among all valid NHIs sharing its three-letter prefix
+    ///
+    /// Valid NHIs are enumerated old format first, then new format, each in ascending order,
+    /// so `ZAA0008` is `0` for the `ZAA` prefix. This order is stable across versions of this
+    /// crate, so indices may be persisted. [NHI::from_index_within_prefix] is the inverse.
+    ///
+    /// Returns `None` only for an invalid NHI created with [NHI::from_trusted], rather than
+    /// panicking, so the index of any parsed NHI is always `Some`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZAC5361".parse().unwrap();
+    /// let index = nhi.index_within_prefix().unwrap();
+    /// assert_eq!(NHI::from_index_within_prefix("ZAC", index), Some(nhi));
+    /// ```
+    pub fn index_within_prefix(&self) -> Option<u32> {
+        let prefix = self.0.get(..3)?;
+        let prefix_sum = checksum(prefix)?;
+        let rest = self.0.get(3..6)?;
+        if let Ok(digits) = rest.parse::<u32>() {
+            Some((0..digits).filter(|&d| has_check_digit(prefix_sum, d)).count() as u32)
+        } else {
+            let digits: u32 = self.0.get(3..5)?.parse().ok()?;
+            let letter = LETTERS.iter().position(|&l| Some(&l) == self.0.as_bytes().get(5))?;
+            Some(old_count(prefix) + digits * 24 + letter as u32)
+        }
+    }
+
+    /// Returns the valid NHI at the given position among those sharing the given three-letter
+    /// prefix, in the order described by [NHI::index_within_prefix]
+    ///
+    /// The prefix is case-insensitive. Returns `None` if the prefix is not three legal letters
+    /// or the index is past the last valid NHI sharing the prefix.
+    pub fn from_index_within_prefix(prefix: &str, index: u32) -> Option<NHI> {
+        let prefix = parse_prefix(prefix)?;
+        let old_count = old_count(&prefix);
+        if index < old_count {
+            let prefix_sum = checksum(&prefix).unwrap();
+            (0..1000)
+                .filter(|&d| has_check_digit(prefix_sum, d))
+                .nth(index as usize)
+                .and_then(|d| old_nhi(&prefix, d))
+        } else if index - old_count < NEW_PER_PREFIX {
+            let index = index - old_count;
+            Some(new_nhi(&prefix, index / 24, index % 24))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the smallest valid NHI of the same format that is greater than this NHI, or
+    /// `None` if this is the greatest valid NHI of its format
+    ///
+    /// The body following the prefix is incremented, skipping old format bodies with no valid
+    /// check digit, and the check character is recomputed. Once the bodies of a prefix are
+    /// exhausted, the next prefix is used. [NHI::prev] steps the other way.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "ZAA0008".parse().unwrap();
+    /// let new: NHI = "ZAA99ZJ".parse().unwrap();
+    /// assert_eq!(old.next().unwrap().as_str(), "ZAA0016");
+    /// assert_eq!(new.next().unwrap().as_str(), "ZAB00AW");
+    /// ```
+    pub fn next(&self) -> Option<NHI> {
+        let prefix = self.prefix_ordinal();
+        match self.format() {
+            NhiFormat::Old => {
+                let digits: u32 = self.0[3..6].parse().unwrap();
+                let rest = (digits + 1..1000).map(move |d| (prefix, d));
+                let later = (prefix + 1..PREFIXES).flat_map(|p| (0..1000).map(move |d| (p, d)));
+                rest.chain(later).find_map(|(p, d)| old_nhi(&prefix_at(p), d))
+            }
+            NhiFormat::New => match self.new_position() + 1 {
+                NEW_PER_PREFIX if prefix + 1 == PREFIXES => None,
+                NEW_PER_PREFIX => Some(new_nhi(&prefix_at(prefix + 1), 0, 0)),
+                i => Some(new_nhi(&self.0[..3], i / 24, i % 24)),
+            },
+        }
+    }
+
+    /// Returns the greatest valid NHI of the same format that is less than this NHI, or `None`
+    /// if this is the smallest valid NHI of its format, see [NHI::next]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZAA0016".parse().unwrap();
+    /// assert_eq!(nhi.prev().unwrap().as_str(), "ZAA0008");
+    /// assert_eq!(nhi.prev().unwrap().next(), Some(nhi));
+    /// ```
+    pub fn prev(&self) -> Option<NHI> {
+        let prefix = self.prefix_ordinal();
+        match self.format() {
+            NhiFormat::Old => {
+                let digits: u32 = self.0[3..6].parse().unwrap();
+                let rest = (0..digits).rev().map(move |d| (prefix, d));
+                let earlier = (0..prefix).rev().flat_map(|p| (0..1000).rev().map(move |d| (p, d)));
+                rest.chain(earlier).find_map(|(p, d)| old_nhi(&prefix_at(p), d))
+            }
+            NhiFormat::New => match self.new_position() {
+                0 if prefix == 0 => None,
+                0 => Some(new_nhi(&prefix_at(prefix - 1), 99, 23)),
+                i => Some(new_nhi(&self.0[..3], (i - 1) / 24, (i - 1) % 24)),
+            },
+        }
+    }
+
+    /// Returns the position of this new format NHI's body among the new format bodies sharing
+    /// its prefix
+    fn new_position(&self) -> u32 {
+        let digits: u32 = self.0[3..5].parse().unwrap();
+        let letter = LETTERS.iter().position(|&l| l == self.0.as_bytes()[5]).unwrap() as u32;
+        digits * 24 + letter
+    }
+
+    /// Returns every valid NHI of the given format sharing the given three-letter prefix, in
+    /// ascending order
+    ///
+    /// Old format bodies with no valid check digit are skipped, so a prefix has fewer than 1000
+    /// old format NHIs but always exactly 2400 (100 digit pairs × 24 letters) new format NHIs.
+    /// The prefix is case-insensitive, and a prefix that is not three legal letters yields
+    /// nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// let mut old = NHI::iter_with_prefix("zaa", NhiFormat::Old);
+    /// assert_eq!(old.next().unwrap().as_str(), "ZAA0008");
+    /// assert_eq!(NHI::iter_with_prefix("ZAA", NhiFormat::New).count(), 2400);
+    /// assert_eq!(NHI::iter_with_prefix("ZIA", NhiFormat::Old).count(), 0);
+    /// ```
+    pub fn iter_with_prefix(prefix: &str, format: NhiFormat) -> impl Iterator<Item = NHI> {
+        let prefix = parse_prefix(prefix);
+        let (old, new) = match (&prefix, format) {
+            (None, _) => (0..0, 0..0),
+            (Some(_), NhiFormat::Old) => (0..1000, 0..0),
+            (Some(_), NhiFormat::New) => (0..0, 0..NEW_PER_PREFIX),
+        };
+        let prefix = prefix.unwrap_or_default();
+        let old_prefix = prefix.clone();
+        old.filter_map(move |digits| old_nhi(&old_prefix, digits))
+            .chain(new.map(move |i| new_nhi(&prefix, i / 24, i % 24)))
+    }
+}
+
+/// Returns every valid NHI of the given format sharing the given three-letter prefix exactly
+/// once, in a random order
+///
+/// Every valid value in the block appears exactly once, so this suits generating test data that
+/// should neither look sequential nor contain duplicates. The prefix is case-insensitive, and a
+/// prefix that is not three legal letters yields nothing. Requires the `rand` feature.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{shuffled_block, NhiFormat};
+///
+/// let nhis: Vec<_> = shuffled_block("ZAA", NhiFormat::New, &mut rand::rng()).collect();
+/// assert_eq!(nhis.len(), 2400);
+/// assert!(nhis.iter().all(|nhi| nhi.as_str().starts_with("ZAA")));
+/// ```
+#[cfg(feature = "rand")]
+pub fn shuffled_block<R: Rng + ?Sized>(
+    prefix: &str,
+    format: NhiFormat,
+    rng: &mut R,
+) -> impl Iterator<Item = NHI> {
+    let prefix = parse_prefix(prefix);
+    let indices = match (&prefix, format) {
+        (None, _) => 0..0,
+        (Some(prefix), NhiFormat::Old) => 0..old_count(prefix),
+        (Some(prefix), NhiFormat::New) => old_count(prefix)..old_count(prefix) + NEW_PER_PREFIX,
+    };
+    let mut indices: Vec<u32> = indices.collect();
+    indices.shuffle(rng);
+    let prefix = prefix.unwrap_or_default();
+    indices
+        .into_iter()
+        .map(move |i| NHI::from_index_within_prefix(&prefix, i).unwrap())
+}
+
+/// Finds a different valid NHI of the same format with the same checksum fingerprint as the given
+/// NHI, i.e. the same weighted sum of its first six characters modulo 11 (old format) or 23 (new
+/// format)
+///
+/// Candidates sharing the NHI's prefix are searched in enumeration order (see
+/// [NHI::index_within_prefix]) and the first match is returned, or `None` if there is none.
+///
+/// ***Note:*** This is purely a test-support utility, for checking that downstream systems never
+/// treat checksum equality as identity.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{checksum_collision, NHI};
+///
+/// let nhi: NHI = "ZBN77VL".parse().unwrap();
+/// let collision = checksum_collision(&nhi).unwrap();
+/// assert_ne!(collision, nhi);
+/// assert_eq!(collision.check_as_letter(), nhi.check_as_letter());
+/// ```
+pub fn checksum_collision(of: &NHI) -> Option<NHI> {
+    let target = of.checksum();
+    let format = of.format();
+    (0..)
+        .map_while(|i| NHI::from_index_within_prefix(&of.0[..3], i))
+        .filter(|nhi| nhi.format() == format)
+        .find(|nhi| nhi != of && nhi.checksum() == target)
+}
+
+/// Returns the prefix at the given position in the ordering of all legal prefixes, see
+/// [NHI::prefix_ordinal]
+fn prefix_at(ordinal: u32) -> String {
+    [ordinal / 576, ordinal / 24 % 24, ordinal % 24]
+        .iter()
+        .map(|&i| LETTERS[i as usize] as char)
+        .collect()
+}
+
+/// Uppercases the given prefix, returning `None` if it is not exactly three legal letters
+pub(crate) fn parse_prefix(prefix: &str) -> Option<String> {
+    let prefix = prefix.to_ascii_uppercase();
+    let legal = prefix.len() == 3 && prefix.bytes().all(|b| LETTERS.contains(&b));
+    legal.then_some(prefix)
+}
+
+/// Returns the old format NHI with the given prefix and three body digits, if it has a valid
+/// check digit
+pub(crate) fn old_nhi(prefix: &str, digits: u32) -> Option<NHI> {
+    let body = format!("{prefix}{digits:03}");
+    let check = expected_check_char(&body)?;
+    Some(NHI::new_normalized(format!("{body}{check}")))
+}
+
+/// Returns the new format NHI with the given prefix, two body digits and body letter index
+pub(crate) fn new_nhi(prefix: &str, digits: u32, letter: u32) -> NHI {
+    let body = format!("{prefix}{digits:02}{}", LETTERS[letter as usize] as char);
+    let check = expected_check_char(&body).unwrap();
+    NHI::new_normalized(format!("{body}{check}"))
+}
+
+/// Returns the number of valid old format NHIs sharing the given prefix
+fn old_count(prefix: &str) -> u32 {
+    let prefix_sum = checksum(prefix).unwrap();
+    (0..1000).filter(|&d| has_check_digit(prefix_sum, d)).count() as u32
+}
+
+/// Returns whether an old format body with the given prefix checksum and three body digits has
+/// a check digit, without allocating
+fn has_check_digit(prefix_sum: u32, digits: u32) -> bool {
+    !(prefix_sum + digits / 100 * 4 + digits / 10 % 10 * 3 + digits % 10 * 2).is_multiple_of(11)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn indices_round_trip_for_known_nhis() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let index = nhi.index_within_prefix().unwrap();
+            assert_eq!(NHI::from_index_within_prefix(&nhi_str.to_lowercase()[..3], index), Some(nhi));
+        }
+    }
+
+    #[test]
+    fn indices_densely_enumerate_a_prefix_in_order() {
+        let nhis: Vec<NHI> = (0..)
+            .map_while(|i| NHI::from_index_within_prefix("ZAA", i))
+            .collect();
+        assert_eq!(nhis.len() as u32, old_count("ZAA") + NEW_PER_PREFIX);
+        for (i, nhi) in nhis.iter().enumerate() {
+            assert!(crate::is_nhi(nhi.as_str()));
+            assert!(nhi.as_str().starts_with("ZAA"));
+            assert_eq!(nhi.index_within_prefix(), Some(i as u32));
+        }
+        let (old, new) = nhis.split_at(old_count("ZAA") as usize);
+        assert!(old.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(new.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(old[0].as_str(), "ZAA0008");
+    }
+
+    #[test]
+    fn every_valid_nhi_with_a_prefix_is_iterated_in_order() {
+        let old: Vec<NHI> = NHI::iter_with_prefix("ZAA", NhiFormat::Old).collect();
+        let with_check_digit = (0..1000)
+            .filter(|d| ('0'..='9').any(|c| crate::is_nhi(&format!("ZAA{d:03}{c}"))))
+            .count();
+        assert_eq!(old.len(), with_check_digit);
+        assert!(old.len() < 1000);
+        let new: Vec<NHI> = NHI::iter_with_prefix("zaa", NhiFormat::New).collect();
+        assert_eq!(new.len(), 2400);
+        for (nhis, format) in [(&old, NhiFormat::Old), (&new, NhiFormat::New)] {
+            assert!(nhis.windows(2).all(|pair| pair[0] < pair[1]));
+            for nhi in nhis {
+                assert!(crate::is_nhi(nhi.as_str()));
+                assert!(nhi.as_str().starts_with("ZAA"));
+                assert_eq!(nhi.format(), format);
+            }
+        }
+        let indexed: Vec<NHI> = (0..).map_while(|i| NHI::from_index_within_prefix("ZAA", i)).collect();
+        assert_eq!(indexed, [old, new].concat());
+    }
+
+    #[test]
+    fn stepping_yields_the_adjacent_valid_nhis_of_the_same_format() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let (next, prev) = (nhi.next().unwrap(), nhi.prev().unwrap());
+            for adjacent in [&next, &prev] {
+                assert!(crate::is_nhi(adjacent.as_str()));
+                assert_eq!(adjacent.format(), nhi.format());
+            }
+            assert!(prev < nhi && nhi < next);
+            assert_eq!(next.prev().as_ref(), Some(&nhi));
+            assert_eq!(prev.next().as_ref(), Some(&nhi));
+        }
+        for format in [NhiFormat::Old, NhiFormat::New] {
+            let nhis: Vec<NHI> = NHI::iter_with_prefix("ZAC", format)
+                .chain(NHI::iter_with_prefix("ZAD", format))
+                .collect();
+            for pair in nhis.windows(2) {
+                assert_eq!(pair[0].next().as_ref(), Some(&pair[1]));
+                assert_eq!(pair[1].prev().as_ref(), Some(&pair[0]));
+            }
+        }
+    }
+
+    #[test]
+    fn stepping_terminates_at_the_ends_of_each_format() {
+        for format in [NhiFormat::Old, NhiFormat::New] {
+            let last = NHI::iter_with_prefix("ZZZ", format).last().unwrap();
+            let first = NHI::iter_with_prefix("AAA", format).next().unwrap();
+            assert_eq!(last.next(), None);
+            assert_eq!(first.prev(), None);
+            let mut nhi = NHI::iter_with_prefix("ZZZ", format).nth(10).unwrap();
+            let mut steps = 0;
+            while let Some(next) = nhi.next() {
+                nhi = next;
+                steps += 1;
+            }
+            assert_eq!(nhi, last);
+            assert!(steps < NHI::iter_with_prefix("ZZZ", format).count());
+        }
+        assert_eq!(prefix_at(0), "AAA");
+        assert_eq!(prefix_at(PREFIXES - 1), "ZZZ");
+    }
+
+    #[test]
+    fn checksum_collisions_are_distinct_nhis_of_the_same_format_and_fingerprint() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let collision = checksum_collision(&nhi).unwrap();
+            assert_ne!(collision, nhi);
+            assert!(crate::is_nhi(collision.as_str()));
+            assert_eq!(collision.format(), nhi.format());
+            assert_eq!(collision.checksum(), nhi.checksum());
+            assert_eq!(collision.check_display(), nhi.check_display());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn shuffled_blocks_hold_every_valid_nhi_of_the_format_once() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        for format in [NhiFormat::Old, NhiFormat::New] {
+            let shuffled: Vec<NHI> = shuffled_block("zac", format, &mut rng).collect();
+            let mut sorted = shuffled.clone();
+            sorted.sort();
+            let expected: Vec<NHI> = (0..)
+                .map_while(|i| NHI::from_index_within_prefix("ZAC", i))
+                .filter(|nhi| nhi.format() == format)
+                .collect();
+            assert_eq!(sorted, expected);
+            assert_ne!(shuffled, expected);
+        }
+        assert_eq!(shuffled_block("ZIA", NhiFormat::Old, &mut rng).count(), 0);
+    }
+
+    #[test]
+    fn invalid_prefixes_have_no_indices() {
+        for prefix in ["", "ZA", "ZAAA", "ZIA", "Z1A", "ÑAA"] {
+            assert_eq!(NHI::from_index_within_prefix(prefix, 0), None);
+            assert_eq!(NHI::iter_with_prefix(prefix, NhiFormat::Old).count(), 0);
+            assert_eq!(NHI::iter_with_prefix(prefix, NhiFormat::New).count(), 0);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+//! Helpers for extracting NHI numbers embedded in larger records.
+
+use alloc::format;
+use alloc::string::String;
+use core::ops::Range;
+
+use crate::normalize::narrow;
+use crate::{infer_check_char, normalize_with_log, ParseNhiError, NHI};
+
+/// Extracts and validates an NHI from a column range of a fixed-width record
+///
+/// The range is **byte-based**, matching the column offsets used by fixed-width file
+/// specifications. Space padding is trimmed from both ends of the field before validation.
+///
+/// # Arguments
+///
+/// * `line`: a fixed-width record
+/// * `range`: the byte range of the NHI field within the record
+///
+/// returns: `Some(NHI)` if the field holds a valid NHI, or `None` if it does not, or if the
+/// range is out of bounds or does not fall on char boundaries
+///
+/// # Examples
+///
+/// ```
+/// use nhi::nhi_from_fixed_width;
+///
+/// let record = "0001 ZAC5361  SMITH";
+/// assert_eq!(nhi_from_fixed_width(record, 5..14).unwrap().as_str(), "ZAC5361");
+/// assert_eq!(nhi_from_fixed_width(record, 5..40), None);
+/// ```
+pub fn nhi_from_fixed_width(line: &str, range: Range<usize>) -> Option<NHI> {
+    line.get(range)?.trim_matches(' ').parse().ok()
+}
+
+/// Extracts and validates the NHI field of a structured QR code payload, such as
+/// `|NHI=ZBN77VL|DOB=1990-01-01|`
+///
+/// The payload is assumed to be a list of fields delimited by `|`, each of which is a `KEY=VALUE`
+/// pair split on its first `=`. Fields may appear in any order, keys are matched
+/// case-insensitively, and whitespace around keys and values is trimmed. Empty fields and fields
+/// without an `=` are ignored.
+///
+/// # Arguments
+///
+/// * `payload`: the decoded text of a QR code
+///
+/// returns: `Some(NHI)` if the payload has exactly one `NHI` field holding a valid NHI, or `None`
+/// if it has no `NHI` field, more than one, or an invalid one
+///
+/// # Examples
+///
+/// ```
+/// use nhi::nhi_from_structured_qr;
+///
+/// let nhi = nhi_from_structured_qr("|DOB=1990-01-01|NHI=ZBN77VL|").unwrap();
+/// assert_eq!(nhi.as_str(), "ZBN77VL");
+/// assert_eq!(nhi_from_structured_qr("|NHI=ZZZ0044|"), None);
+/// assert_eq!(nhi_from_structured_qr("|DOB=1990-01-01|"), None);
+/// ```
+pub fn nhi_from_structured_qr(payload: &str) -> Option<NHI> {
+    let mut values = payload
+        .split('|')
+        .filter_map(|field| field.split_once('='))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("NHI"))
+        .map(|(_, value)| value.trim());
+    let value = values.next()?;
+    if values.next().is_some() {
+        return None;
+    }
+    value.parse().ok()
+}
+
+/// Checks an NHI entered as two separate fields, its three-letter prefix and the remaining four
+/// characters, see [parse_split]
+///
+/// # Examples
+///
+/// ```
+/// use nhi::is_nhi_split;
+///
+/// assert!(is_nhi_split("ZAC", "5361"));
+/// assert!(is_nhi_split("zbn", "77vl"));
+/// assert!(!is_nhi_split("ZB", "N77VL"));
+/// ```
+pub fn is_nhi_split(alpha: &str, rest: &str) -> bool {
+    parse_split(alpha, rest).is_ok()
+}
+
+/// Parses an NHI entered as two separate fields, its three-letter prefix and the remaining four
+/// characters
+///
+/// For old format NHIs the remaining characters are the four digits (e.g. `"ZAC"` and
+/// `"5361"`), and for new format NHIs they are the two digits followed by the two letters (e.g.
+/// `"ZBN"` and `"77VL"`). Each field is normalized with [normalize_with_log] before they are
+/// combined and validated.
+///
+/// # Arguments
+///
+/// * `alpha`: the three-letter prefix field
+/// * `rest`: the field holding the remaining four characters
+///
+/// returns: Result<NHI, ParseNhiError>, with [ParseNhiError::InvalidFormat] if the normalized
+/// prefix field is not three characters long, even if the combined value would be valid
+///
+/// # Examples
+///
+/// ```
+/// use nhi::parse_split;
+///
+/// assert_eq!(parse_split(" zbn ", "77-VL").unwrap().as_str(), "ZBN77VL");
+/// assert!(parse_split("ZBN7", "7VL").is_err());
+/// ```
+pub fn parse_split(alpha: &str, rest: &str) -> Result<NHI, ParseNhiError> {
+    let (alpha, _) = normalize_with_log(alpha);
+    let (rest, _) = normalize_with_log(rest);
+    if alpha.chars().count() != 3 {
+        return Err(ParseNhiError::InvalidFormat);
+    }
+    format!("{alpha}{rest}").parse()
+}
+
+impl NHI {
+    /// Assembles an NHI from its three-letter prefix and the following three characters,
+    /// computing and appending its check digit or check character
+    ///
+    /// The format is chosen by the body: three digits (e.g. `"536"`) make an old format NHI and
+    /// two digits followed by a letter (e.g. `"77V"`) a new format NHI. See [infer_check_char].
+    ///
+    /// # Arguments
+    ///
+    /// * `prefix`: the three-letter prefix, checked case-insensitively
+    /// * `body`: the three characters following the prefix, excluding the check character
+    ///
+    /// returns: Result<NHI, ParseNhiError>, with [ParseNhiError::InvalidFormat] if the prefix is
+    /// not three characters long (as for [parse_split]), [ParseNhiError::InvalidLength] if the
+    /// body is not three characters long, and otherwise the errors of [infer_check_char]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{ParseNhiError, NHI};
+    ///
+    /// assert_eq!(NHI::from_parts("ZAC", "536").unwrap().as_str(), "ZAC5361");
+    /// assert_eq!(NHI::from_parts("zbn", "77v").unwrap().as_str(), "ZBN77VL");
+    /// assert_eq!(NHI::from_parts("ZZZ", "004"), Err(ParseNhiError::NoValidCheckDigit));
+    /// ```
+    pub fn from_parts(prefix: &str, body: &str) -> Result<NHI, ParseNhiError> {
+        if prefix.chars().count() != 3 {
+            return Err(ParseNhiError::InvalidFormat);
+        }
+        if body.chars().count() != 3 {
+            return Err(ParseNhiError::InvalidLength);
+        }
+        let body = format!("{prefix}{body}");
+        let check = infer_check_char(&body)?;
+        format!("{body}{check}").parse()
+    }
+
+    /// Parses an NHI from a scanned barcode payload, ignoring the noise scanners add at its
+    /// boundaries
+    ///
+    /// Full-width characters are first mapped to ASCII, as some scanners emit them. Surrounding
+    /// whitespace and ASCII control characters (e.g. a trailing `\0`) are then trimmed, an
+    /// optional case-insensitive `NHI:` or `NHI ` label is removed and the remainder trimmed
+    /// again. Characters in the middle of the value are never removed, so unlike
+    /// [NHI::parse_normalized] interior separators are still rejected. As NHIs never contain
+    /// the letter `I`, a label cannot be confused with the start of a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential NHI string, possibly labelled and padded
+    ///
+    /// returns: Result<NHI, ParseNhiError>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// assert_eq!(NHI::parse_lenient("NHI:ZBN77VL").unwrap().as_str(), "ZBN77VL");
+    /// assert_eq!(NHI::parse_lenient(" nhi zbn77vl\0\r\n").unwrap().as_str(), "ZBN77VL");
+    /// assert!(NHI::parse_lenient("ZBN77VL NHI:").is_err());
+    /// ```
+    pub fn parse_lenient(s: &str) -> Result<NHI, ParseNhiError> {
+        let noise = |c: char| c.is_whitespace() || c.is_ascii_control();
+        let narrowed: String = s.chars().map(narrow).collect();
+        let s = narrowed.trim_matches(noise);
+        let unlabelled = s
+            .get(..4)
+            .filter(|label| label.eq_ignore_ascii_case("NHI:") || label.eq_ignore_ascii_case("NHI "))
+            .map_or(s, |_| &s[4..]);
+        unlabelled.trim_matches(noise).parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn fixed_width_fields_are_trimmed_and_validated() {
+        let record = "0001ZAC5361 zbn77vl ZZZ0044";
+        assert_eq!(nhi_from_fixed_width(record, 4..11).unwrap().as_str(), "ZAC5361");
+        assert_eq!(nhi_from_fixed_width(record, 11..20).unwrap().as_str(), "ZBN77VL");
+        assert_eq!(nhi_from_fixed_width(record, 20..27), None);
+        assert_eq!(nhi_from_fixed_width(record, 0..11), None);
+        assert_eq!(nhi_from_fixed_width(" \tZAC5361", 0..9), None);
+    }
+
+    #[test]
+    fn out_of_range_and_non_char_boundary_ranges_are_rejected() {
+        let record = "ZAC5361";
+        assert_eq!(nhi_from_fixed_width(record, 0..8), None);
+        assert_eq!(nhi_from_fixed_width(record, 8..9), None);
+        assert_eq!(nhi_from_fixed_width(record, Range { start: 7, end: 0 }), None);
+        assert_eq!(nhi_from_fixed_width("éZAC5361", 1..8), None);
+    }
+
+    #[test]
+    fn structured_qr_fields_are_found_in_any_order() {
+        for payload in [
+            "|NHI=ZBN77VL|DOB=1990-01-01|",
+            "DOB=1990-01-01|NHI=ZBN77VL",
+            "| dob = 1990-01-01 | nhi = zbn77vl |",
+            "NAME=A=B|NHI=ZBN77VL||FLAG|",
+        ] {
+            assert_eq!(nhi_from_structured_qr(payload).unwrap().as_str(), "ZBN77VL");
+        }
+    }
+
+    #[test]
+    fn structured_qr_payloads_without_exactly_one_valid_nhi_are_rejected() {
+        for payload in [
+            "",
+            "|",
+            "ZBN77VL",
+            "|DOB=1990-01-01|",
+            "|NHI=|",
+            "|NHI=ZZZ0044|",
+            "|NHI=ZBN77VL=|",
+            "|NHI_ALT=ZBN77VL|",
+            "|NHI=ZBN77VL|NHI=ZAC5361|",
+            "|NHI=ZBN77VL|NHI=ZBN77VL|",
+        ] {
+            assert_eq!(nhi_from_structured_qr(payload), None);
+        }
+    }
+
+    #[test]
+    fn split_fields_are_normalized_and_combined() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let (alpha, rest) = nhi_str.split_at(3);
+            assert_eq!(parse_split(alpha, rest).unwrap().as_str(), *nhi_str);
+            let padded = format!(" {} ", rest.to_lowercase());
+            assert_eq!(parse_split(&alpha.to_lowercase(), &padded).unwrap().as_str(), *nhi_str);
+            assert!(is_nhi_split(alpha, rest));
+        }
+    }
+
+    #[test]
+    fn split_fields_must_each_hold_their_part() {
+        assert_eq!(parse_split("ZA", "C5361"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(parse_split("ZAC5", "361"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(parse_split("", "ZAC5361"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(parse_split("ZAC", "536"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(parse_split("ZZZ", "0044"), Err(ParseNhiError::NoValidCheckDigit));
+        assert!(!is_nhi_split("ZAC", ""));
+    }
+
+    #[test]
+    fn parts_are_assembled_with_their_check_character() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let (prefix, body) = nhi_str[..6].split_at(3);
+            assert_eq!(NHI::from_parts(prefix, body).unwrap().as_str(), *nhi_str);
+            let nhi = NHI::from_parts(&prefix.to_lowercase(), &body.to_lowercase()).unwrap();
+            assert_eq!(nhi.as_str(), *nhi_str);
+        }
+    }
+
+    #[test]
+    fn scanner_noise_is_trimmed_from_the_boundaries() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            for payload in [
+                format!("NHI:{nhi_str}"),
+                format!("nhi: {}", nhi_str.to_lowercase()),
+                format!("Nhi {nhi_str}"),
+                format!("{nhi_str}\0"),
+                format!("\x02 NHI:{nhi_str}\r\n\0\0"),
+                nhi_str.to_string(),
+            ] {
+                assert_eq!(NHI::parse_lenient(&payload).unwrap().as_str(), *nhi_str);
+            }
+        }
+    }
+
+    #[test]
+    fn interior_noise_is_not_ignored_by_lenient_parsing() {
+        assert_eq!(NHI::parse_lenient("ZBN77VL NHI:"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient("ZBNNHI:77VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient("ZBN\x0077VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient("ZBN 77VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient("NHI:NHI:ZBN77VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient("NHI-ZBN77VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient(""), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::parse_lenient("ＺＢＮ７７ＶＬ\r\n").unwrap().as_str(), "ZBN77VL");
+        assert_eq!(NHI::parse_lenient("ＮＨＩ：ｚｂｎ７７ｖｌ").unwrap().as_str(), "ZBN77VL");
+        assert!("NHI:ZBN77VL".parse::<NHI>().is_err());
+        assert!("ZBN77VL\0".parse::<NHI>().is_err());
+    }
+
+    #[test]
+    fn invalid_parts_are_rejected() {
+        assert_eq!(NHI::from_parts("ZA", "C536"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(NHI::from_parts("ZACX", "536"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(NHI::from_parts("ZAC", "53"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::from_parts("ZAC", "5361"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::from_parts("ZA1", "536"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(NHI::from_parts("ZAI", "536"), Err(ParseNhiError::ForbiddenLetter { letter: 'I', index: 2 }));
+        assert_eq!(NHI::from_parts("ZAC", "5A6"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(NHI::from_parts("ZBN", "77O"), Err(ParseNhiError::ForbiddenLetter { letter: 'O', index: 5 }));
+        assert_eq!(NHI::from_parts("ZBN", "77-"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(NHI::from_parts("ZZZ", "004"), Err(ParseNhiError::NoValidCheckDigit));
+    }
+}
@@ -0,0 +1,97 @@
+//! A C interface to NHI validation, for services written in other languages.
+//!
+//! The functions are declared in `include/nhi.h`. Build a library to link against with e.g.
+//! `cargo rustc --release --features ffi --crate-type cdylib` (or `staticlib`).
+
+use core::ffi::{c_char, c_int, CStr};
+
+use crate::{NhiFormat, NHI};
+
+/// Checks a null-terminated UTF-8 string with [is_nhi](crate::is_nhi). Requires the `ffi`
+/// feature.
+///
+/// returns: `true` if the string is a valid NHI, and `false` otherwise, including when the
+/// pointer is null or the string is not valid UTF-8
+///
+/// # Safety
+///
+/// `nhi` must be null or point to a null-terminated string that is valid for reads and is not
+/// modified for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn nhi_is_valid(nhi: *const c_char) -> bool {
+    to_str(nhi).is_some_and(crate::is_nhi)
+}
+
+/// Returns the format of a null-terminated UTF-8 string if it is a valid NHI. Requires the
+/// `ffi` feature.
+///
+/// returns: `0` for a valid old format NHI, `1` for a valid new format NHI, and `-1` otherwise,
+/// including when the pointer is null or the string is not valid UTF-8
+///
+/// # Safety
+///
+/// `nhi` must be null or point to a null-terminated string that is valid for reads and is not
+/// modified for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn nhi_format(nhi: *const c_char) -> c_int {
+    match to_str(nhi).and_then(|s| s.parse::<NHI>().ok()).map(|nhi| nhi.format()) {
+        Some(NhiFormat::Old) => 0,
+        Some(NhiFormat::New) => 1,
+        None => -1,
+    }
+}
+
+/// Borrows a null-terminated string as a `&str`, or returns `None` if the pointer is null or
+/// the string is not valid UTF-8
+///
+/// # Safety
+///
+/// See [nhi_is_valid].
+unsafe fn to_str<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::ptr;
+
+    use super::*;
+    use crate::tests::{INVALID_NEW, INVALID_OLD, RANDOM_STRINGS, VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn valid_nhis_are_accepted_through_the_c_interface() {
+        for (nhi_str, code) in VALID_OLD.iter().map(|s| (s, 0)).chain(VALID_NEW.iter().map(|s| (s, 1))) {
+            let nhi = CString::new(nhi_str.to_lowercase()).unwrap();
+            unsafe {
+                assert!(nhi_is_valid(nhi.as_ptr()));
+                assert_eq!(nhi_format(nhi.as_ptr()), code);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_nhis_are_rejected_through_the_c_interface() {
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            let nhi = CString::new(*nhi_str).unwrap();
+            unsafe {
+                assert!(!nhi_is_valid(nhi.as_ptr()));
+                assert_eq!(nhi_format(nhi.as_ptr()), -1);
+            }
+        }
+    }
+
+    #[test]
+    fn null_and_non_utf8_strings_are_rejected() {
+        let non_utf8 = CString::new(b"ZBN77V\xff".to_vec()).unwrap();
+        unsafe {
+            assert!(!nhi_is_valid(ptr::null()));
+            assert_eq!(nhi_format(ptr::null()), -1);
+            assert!(!nhi_is_valid(non_utf8.as_ptr()));
+            assert_eq!(nhi_format(non_utf8.as_ptr()), -1);
+        }
+    }
+}
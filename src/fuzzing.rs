@@ -0,0 +1,94 @@
+//! Generators of valid NHI numbers for fuzzing and property-based testing.
+
+use alloc::string::String;
+
+#[cfg(feature = "arbitrary")]
+use arbitrary::{Arbitrary, Unstructured};
+#[cfg(feature = "proptest")]
+use proptest::prelude::*;
+
+use crate::enumerate::{new_nhi, old_nhi};
+use crate::{LETTERS, NHI};
+
+#[cfg(feature = "arbitrary")]
+impl<'a> Arbitrary<'a> for NHI {
+    /// Generates a valid NHI of either format, with any legal prefix including test prefixes.
+    /// Requires the `arbitrary` feature.
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let prefix = [u.choose(LETTERS)?, u.choose(LETTERS)?, u.choose(LETTERS)?];
+        let new = u.arbitrary()?;
+        let digits = u.int_in_range(if new { 0..=99 } else { 0..=999 })?;
+        let letter = u.int_in_range(0..=23)?;
+        Ok(valid_nhi(prefix.map(|&b| b as char), new, digits, letter))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (0, Some(8))
+    }
+}
+
+/// Returns a [proptest] strategy generating valid NHIs of either format, with any legal prefix
+/// including test prefixes. Requires the `proptest` feature.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{is_nhi, nhi_strategy};
+/// use proptest::proptest;
+///
+/// proptest!(|(nhi in nhi_strategy())| {
+///     assert!(is_nhi(nhi.as_str()));
+/// });
+/// ```
+#[cfg(feature = "proptest")]
+pub fn nhi_strategy() -> impl Strategy<Value = NHI> {
+    let letter = || prop::sample::select(&LETTERS[..]).prop_map(char::from);
+    ([letter(), letter(), letter()], any::<bool>(), 0..1000u32, 0..24u32).prop_map(
+        |(prefix, new, digits, letter)| {
+            let digits = if new { digits % 100 } else { digits };
+            valid_nhi(prefix, new, digits, letter)
+        },
+    )
+}
+
+/// Returns the valid NHI with the given prefix and body, skipping to the next body digits for
+/// an old format body with no valid check digit
+fn valid_nhi(prefix: [char; 3], new: bool, digits: u32, letter: u32) -> NHI {
+    let prefix: String = prefix.iter().collect();
+    if new {
+        new_nhi(&prefix, digits, letter)
+    } else {
+        // At most one in every two consecutive old format bodies has no check digit
+        (digits..)
+            .find_map(|digits| old_nhi(&prefix, digits % 1000))
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::is_nhi;
+
+    #[test]
+    #[cfg(feature = "arbitrary")]
+    fn arbitrary_nhis_are_always_valid() {
+        let bytes: Vec<u8> = (0..64_000u64).flat_map(|i| crate::packed::mix(i).to_le_bytes()).collect();
+        let mut u = Unstructured::new(&bytes);
+        let nhis: Vec<NHI> = (0..20_000).map(|_| NHI::arbitrary(&mut u).unwrap()).collect();
+        assert!(nhis.iter().all(|nhi| is_nhi(nhi.as_str())));
+        for format in [crate::NhiFormat::Old, crate::NhiFormat::New] {
+            assert!(nhis.iter().any(|nhi| nhi.format() == format && nhi.is_test()));
+            assert!(nhis.iter().any(|nhi| nhi.format() == format && nhi.is_not_test()));
+        }
+        assert!(is_nhi(NHI::arbitrary(&mut Unstructured::new(&[])).unwrap().as_str()));
+    }
+
+    #[cfg(feature = "proptest")]
+    proptest! {
+        #[test]
+        fn strategy_nhis_are_always_valid(nhi in nhi_strategy()) {
+            prop_assert!(is_nhi(nhi.as_str()));
+        }
+    }
+}
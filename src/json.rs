@@ -0,0 +1,449 @@
+//! Helpers for validating NHI numbers found in JSON documents.
+//!
+//! Requires the `json` feature.
+
+use std::fmt;
+use std::io::{self, BufRead, Bytes};
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::{ParseNhiError, NHI};
+
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+    Wildcard,
+}
+
+/// Validates every value in a JSON document selected by a simple path expression
+///
+/// The supported path syntax is a subset of JSONPath:
+///
+/// * `key` selects the field `key` of an object, with `.` separating successive keys
+///   (e.g. `patient.nhi`)
+/// * `[n]` selects the `n`th element of an array (e.g. `patients[0].nhi`)
+/// * `[*]` selects every element of an array (e.g. `patients[*].nhi`)
+///
+/// Keys containing `.`, `[` or `]` cannot be selected. A selector that cannot be parsed
+/// matches nothing.
+///
+/// Each selected value is paired with its concrete path, with wildcards replaced by the index of
+/// the element they matched (e.g. `patients[2].nhi`). Results are in document order. Selected
+/// values that are not strings are reported as errors. Missing fields and out-of-range indices
+/// are skipped.
+///
+/// # Arguments
+///
+/// * `document`: the JSON document to search
+/// * `path`: the path expression selecting NHI-bearing fields
+///
+/// returns: Vec<(String, Result<NHI, ParseNhiError>)>
+///
+/// # Examples
+///
+/// ```
+/// use nhi::validate_json_path;
+/// use serde_json::json;
+///
+/// let document = json!({"patients": [{"nhi": "ZAC5361"}, {"nhi": "ZZZ0044"}]});
+/// let results = validate_json_path(&document, "patients[*].nhi");
+///
+/// assert_eq!(results[0].0, "patients[0].nhi");
+/// assert_eq!(results[0].1.as_ref().unwrap().as_str(), "ZAC5361");
+/// assert_eq!(results[1].0, "patients[1].nhi");
+/// assert!(results[1].1.is_err());
+/// ```
+pub fn validate_json_path(document: &Value, path: &str) -> Vec<(String, Result<NHI, ParseNhiError>)> {
+    let mut results = Vec::new();
+    if let Some(segments) = parse_path(path) {
+        select(document, &segments, String::new(), &mut results);
+    }
+    results
+}
+
+/// Extracts and validates the NHI at the given JSON Pointer within a JSON document
+///
+/// The pointer uses the standard [RFC 6901](https://www.rfc-editor.org/rfc/rfc6901) syntax: a
+/// sequence of `/`-prefixed reference tokens, each naming an object field or an array index
+/// (e.g. `/patients/0/nhi`), with `~1` escaping `/` and `~0` escaping `~` within tokens. The
+/// empty pointer `""` refers to the whole document.
+///
+/// # Arguments
+///
+/// * `value`: the JSON document to search
+/// * `pointer`: the JSON Pointer to the NHI-bearing field
+///
+/// returns: `Some(NHI)` if the pointer resolves to a string holding a valid NHI, or `None` if
+/// the pointer is malformed or missing, or the value is not a string or not a valid NHI
+///
+/// # Examples
+///
+/// ```
+/// use nhi::nhi_from_json_pointer;
+/// use serde_json::json;
+///
+/// let document = json!({"patients": [{"nhi": "zac5361"}, {"nhi": "ZZZ0044"}]});
+/// assert_eq!(nhi_from_json_pointer(&document, "/patients/0/nhi").unwrap().as_str(), "ZAC5361");
+/// assert_eq!(nhi_from_json_pointer(&document, "/patients/1/nhi"), None);
+/// assert_eq!(nhi_from_json_pointer(&document, "/patients/2/nhi"), None);
+/// ```
+pub fn nhi_from_json_pointer(value: &Value, pointer: &str) -> Option<NHI> {
+    value.pointer(pointer)?.as_str()?.parse().ok()
+}
+
+/// An error reading an element of a JSON array with [JsonNhiStream]
+#[derive(Debug)]
+pub enum JsonStreamError {
+    /// The underlying reader failed, or the input is not a well-formed JSON array. The stream
+    /// always ends after this error.
+    Io(io::Error),
+    /// The element is not a valid NHI. Elements that are not JSON strings are reported as
+    /// [ParseNhiError::InvalidFormat].
+    Nhi(ParseNhiError),
+}
+
+impl fmt::Display for JsonStreamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            JsonStreamError::Io(error) => write!(f, "error reading JSON array: {error}"),
+            JsonStreamError::Nhi(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for JsonStreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            JsonStreamError::Io(error) => Some(error),
+            JsonStreamError::Nhi(error) => Some(error),
+        }
+    }
+}
+
+/// Validates the elements of a JSON array of NHI strings as they are read, without reading the
+/// whole array into memory
+///
+/// Each element is buffered only while it is read and validated, so memory use is bounded by
+/// the largest single element rather than the size of the array. Elements are deserialized
+/// with `serde_json`, one at a time.
+///
+/// By default, an element that is not a valid NHI is yielded as a [JsonStreamError::Nhi] error
+/// and reading continues with the next element. Use [JsonNhiStream::stop_on_error] to end the
+/// stream at the first error instead. Errors reading the array itself, such as a missing `[`,
+/// unbalanced brackets, a missing `,` between elements or the input ending before the closing
+/// `]`, always end the stream. Anything after the
+/// closing `]` is not read. Requires the `json` feature.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::JsonNhiStream;
+///
+/// let input = r#"["ZAC5361", "ZZZ0044", {"nhi": "ZBN77VL"}, "zbn77vl"]"#;
+/// let results: Vec<_> = JsonNhiStream::new(input.as_bytes()).collect();
+///
+/// assert_eq!(results.len(), 4);
+/// assert_eq!(results[0].as_ref().unwrap().as_str(), "ZAC5361");
+/// assert!(results[1].is_err());
+/// assert!(results[2].is_err());
+/// assert_eq!(results[3].as_ref().unwrap().as_str(), "ZBN77VL");
+/// ```
+pub struct JsonNhiStream<R> {
+    bytes: Bytes<R>,
+    started: bool,
+    done: bool,
+    stop_on_error: bool,
+}
+
+impl<R: BufRead> JsonNhiStream<R> {
+    /// Creates a stream over the JSON array read from the given reader
+    pub fn new(reader: R) -> Self {
+        JsonNhiStream { bytes: reader.bytes(), started: false, done: false, stop_on_error: false }
+    }
+
+    /// Sets whether the stream ends after the first element that is not a valid NHI, rather
+    /// than continuing with the next element
+    pub fn stop_on_error(mut self, stop_on_error: bool) -> Self {
+        self.stop_on_error = stop_on_error;
+        self
+    }
+
+    /// Returns the next byte that is not JSON whitespace
+    fn next_non_whitespace(&mut self) -> io::Result<u8> {
+        loop {
+            match self.bytes.next() {
+                Some(Ok(b' ' | b'\t' | b'\n' | b'\r')) => continue,
+                Some(result) => return result,
+                None => return Err(io::ErrorKind::UnexpectedEof.into()),
+            }
+        }
+    }
+
+    /// Reads the raw bytes of the next element, starting with the given byte, returning them
+    /// along with whether the element ended the array
+    fn read_element(&mut self, first: u8) -> io::Result<(Vec<u8>, bool)> {
+        let mut element = Vec::new();
+        // The brackets and braces opened within the element and not yet closed
+        let mut openers = Vec::new();
+        let (mut in_string, mut escaped) = (false, false);
+        let mut next = Some(first);
+        loop {
+            let b = match next.take() {
+                Some(b) => b,
+                None => self.bytes.next().ok_or(io::ErrorKind::UnexpectedEof)??,
+            };
+            if in_string {
+                (in_string, escaped) = (escaped || b != b'"', !escaped && b == b'\\');
+            } else {
+                match b {
+                    b'"' => in_string = true,
+                    b'[' | b'{' => openers.push(b),
+                    b']' if openers.is_empty() => return Ok((element, true)),
+                    b',' if openers.is_empty() => return Ok((element, false)),
+                    b']' | b'}' => {
+                        let opener = if b == b']' { b'[' } else { b'{' };
+                        if openers.pop() != Some(opener) {
+                            return Err(io::Error::new(io::ErrorKind::InvalidData, "unbalanced brackets"));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            element.push(b);
+        }
+    }
+
+    fn read_next(&mut self) -> io::Result<Option<Result<NHI, ParseNhiError>>> {
+        let mut first = self.next_non_whitespace()?;
+        if !self.started {
+            self.started = true;
+            if first != b'[' {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "expected a JSON array"));
+            }
+            first = self.next_non_whitespace()?;
+            if first == b']' {
+                self.done = true;
+                return Ok(None);
+            }
+        }
+        let (element, last) = self.read_element(first)?;
+        self.done = last;
+        if element.iter().all(u8::is_ascii_whitespace) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "expected an array element"));
+        }
+        let value = serde_json::from_slice::<Value>(&element)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "malformed array element"))?;
+        let result = value.as_str().ok_or(ParseNhiError::InvalidFormat).and_then(NHI::from_str);
+        Ok(Some(result))
+    }
+}
+
+impl<R: BufRead> Iterator for JsonNhiStream<R> {
+    type Item = Result<NHI, JsonStreamError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.read_next() {
+            Ok(Some(Ok(nhi))) => Some(Ok(nhi)),
+            Ok(Some(Err(error))) => {
+                self.done |= self.stop_on_error;
+                Some(Err(JsonStreamError::Nhi(error)))
+            }
+            Ok(None) => None,
+            Err(error) => {
+                self.done = true;
+                Some(Err(JsonStreamError::Io(error)))
+            }
+        }
+    }
+}
+
+fn parse_path(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, mut rest) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if !key.is_empty() {
+            segments.push(Segment::Key(key));
+        } else if rest.is_empty() || !segments.is_empty() {
+            return None;
+        }
+        while !rest.is_empty() {
+            let end = rest.find(']')?;
+            let index = rest.get(1..end)?;
+            segments.push(match index {
+                "*" => Segment::Wildcard,
+                _ => Segment::Index(index.parse().ok()?),
+            });
+            rest = &rest[end + 1..];
+            if !rest.is_empty() && !rest.starts_with('[') {
+                return None;
+            }
+        }
+    }
+    if segments.is_empty() { None } else { Some(segments) }
+}
+
+fn select(
+    value: &Value,
+    segments: &[Segment],
+    path: String,
+    results: &mut Vec<(String, Result<NHI, ParseNhiError>)>,
+) {
+    let Some((segment, rest)) = segments.split_first() else {
+        let result = value.as_str().ok_or(ParseNhiError::InvalidFormat).and_then(NHI::from_str);
+        results.push((path, result));
+        return;
+    };
+    match (segment, value) {
+        (Segment::Key(key), Value::Object(map)) => {
+            if let Some(child) = map.get(*key) {
+                let path = if path.is_empty() { key.to_string() } else { format!("{path}.{key}") };
+                select(child, rest, path, results);
+            }
+        }
+        (Segment::Index(i), Value::Array(items)) => {
+            if let Some(child) = items.get(*i) {
+                select(child, rest, format!("{path}[{i}]"), results);
+            }
+        }
+        (Segment::Wildcard, Value::Array(items)) => {
+            for (i, child) in items.iter().enumerate() {
+                select(child, rest, format!("{path}[{i}]"), results);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn paths_and_validity(document: &Value, path: &str) -> Vec<(String, bool)> {
+        validate_json_path(document, path)
+            .into_iter()
+            .map(|(path, result)| (path, result.is_ok()))
+            .collect()
+    }
+
+    #[test]
+    fn selectors_traverse_keys_indices_and_wildcards() {
+        let document = json!({
+            "patients": [
+                {"nhi": "ZAC5361", "contacts": [{"nhi": "ZBN77VL"}]},
+                {"nhi": "zzz0044"},
+                {"name": "no nhi"},
+                {"nhi": 1234567},
+            ],
+            "primary": {"nhi": "jbx3656"},
+        });
+        assert_eq!(paths_and_validity(&document, "primary.nhi"), vec![("primary.nhi".into(), true)]);
+        assert_eq!(
+            paths_and_validity(&document, "patients[*].nhi"),
+            vec![
+                ("patients[0].nhi".into(), true),
+                ("patients[1].nhi".into(), false),
+                ("patients[3].nhi".into(), false),
+            ],
+        );
+        assert_eq!(
+            paths_and_validity(&document, "patients[0].contacts[0].nhi"),
+            vec![("patients[0].contacts[0].nhi".into(), true)],
+        );
+        assert!(paths_and_validity(&document, "patients[9].nhi").is_empty());
+        assert!(paths_and_validity(&document, "primary[*]").is_empty());
+    }
+
+    #[test]
+    fn top_level_arrays_can_be_selected() {
+        let document = json!(["ZAC5361", "ZZZ0044"]);
+        assert_eq!(
+            paths_and_validity(&document, "[*]"),
+            vec![("[0]".into(), true), ("[1]".into(), false)],
+        );
+    }
+
+    #[test]
+    fn malformed_selectors_match_nothing() {
+        let document = json!({"patients": [{"nhi": "ZAC5361"}]});
+        for path in ["", "patients[", "patients[x].nhi", "patients[0]x.nhi", "patients..nhi"] {
+            assert!(validate_json_path(&document, path).is_empty(), "{path}");
+        }
+    }
+
+    #[test]
+    fn json_pointers_resolve_to_valid_nhis() {
+        let document = json!({"a/b": {"~": ["ZBN77VL"]}, "patients": [{"nhi": "ZAC5361"}]});
+        let nhi = |pointer: &str| nhi_from_json_pointer(&document, pointer).map(NHI::into_string);
+        assert_eq!(nhi("/patients/0/nhi").as_deref(), Some("ZAC5361"));
+        assert_eq!(nhi("/a~1b/~0/0").as_deref(), Some("ZBN77VL"));
+        assert_eq!(nhi_from_json_pointer(&json!("ZAC5361"), "").unwrap().as_str(), "ZAC5361");
+    }
+
+    #[test]
+    fn json_pointers_to_missing_non_string_or_invalid_values_are_none() {
+        let document = json!({"patients": [{"nhi": "ZZZ0044", "id": 7, "tags": ["ZAC5361"]}]});
+        let pointers = [
+            "", "patients/0/nhi", "/patients/0/nhi", "/patients/0/id", "/patients/0/tags",
+            "/patients/1/nhi", "/missing",
+        ];
+        for pointer in pointers {
+            assert_eq!(nhi_from_json_pointer(&document, pointer), None, "{pointer}");
+        }
+    }
+
+    fn stream(input: &str) -> Vec<Result<String, String>> {
+        JsonNhiStream::new(input.as_bytes())
+            .map(|result| match result {
+                Ok(nhi) => Ok(nhi.into_string()),
+                Err(JsonStreamError::Nhi(error)) => Err(format!("{error:?}")),
+                Err(JsonStreamError::Io(error)) => Err(format!("{:?}", error.kind())),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn streamed_arrays_validate_each_element() {
+        let input = r#" [ "zac5361" ,"ZZZ0044", 7, null, ["ZBN77VL"], {"a": "]"}, "a\"]", "ZBN77VL"] trailing"#;
+        assert_eq!(stream(input), vec![
+            Ok("ZAC5361".into()),
+            Err("NoValidCheckDigit".into()),
+            Err("InvalidFormat".into()),
+            Err("InvalidFormat".into()),
+            Err("InvalidFormat".into()),
+            Err("InvalidFormat".into()),
+            Err("InvalidLength".into()),
+            Ok("ZBN77VL".into()),
+        ]);
+        assert!(stream("[]").is_empty());
+        assert!(stream(" [\n] ").is_empty());
+    }
+
+    #[test]
+    fn streams_can_stop_at_the_first_invalid_element() {
+        let input = r#"["ZAC5361", "ZZZ0044", "ZBN77VL"]"#;
+        let results: Vec<_> = JsonNhiStream::new(input.as_bytes()).stop_on_error(true).collect();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(results[1], Err(JsonStreamError::Nhi(ParseNhiError::NoValidCheckDigit))));
+    }
+
+    #[test]
+    fn malformed_arrays_end_the_stream_with_an_error() {
+        assert_eq!(stream(""), vec![Err("UnexpectedEof".into())]);
+        assert_eq!(stream(r#"{"nhi": "ZAC5361"}"#), vec![Err("InvalidData".into())]);
+        assert_eq!(stream(r#"["ZAC5361""#), vec![Err("UnexpectedEof".into())]);
+        assert_eq!(stream(r#"["ZAC5361", "ZBN77"#), vec![Ok("ZAC5361".into()), Err("UnexpectedEof".into())]);
+        assert_eq!(stream(r#"["ZAC5361",]"#), vec![Ok("ZAC5361".into()), Err("InvalidData".into())]);
+        assert_eq!(stream("[,]"), vec![Err("InvalidData".into())]);
+        assert_eq!(stream("[}]"), vec![Err("InvalidData".into())]);
+        assert_eq!(stream(r#"["ZAC5361"}]"#), vec![Err("InvalidData".into())]);
+        assert_eq!(stream(r#"[["ZAC5361"}, "ZBN77VL"]"#), vec![Err("InvalidData".into())]);
+        assert_eq!(stream(r#"["ZAC5361" "ZBN77VL"]"#), vec![Err("InvalidData".into())]);
+        assert_eq!(stream(r#"["ZAC5361", 7 8]"#), vec![Ok("ZAC5361".into()), Err("InvalidData".into())]);
+    }
+}
@@ -25,7 +25,15 @@
 //! assert_eq!(nhi.as_str(), "ZBN77VL");
 //! ```
 //!
-//! Checks are case-insensitive.
+//! Checks are case-insensitive. NHI literals, e.g. in tests and fixtures, can be checked at
+//! compile time with the [nhi!] macro:
+//!
+//! ```rust
+//! use nhi::nhi;
+//!
+//! let nhi = nhi!("zbn77vl");
+//! assert_eq!(nhi.as_str(), "ZBN77VL");
+//! ```
 //!
 //! ***Note:*** This does not check that the NHI number has been _assigned_ to
 //! a person, it merely checks the NHI is consistent with the HISO 10046:2023
@@ -34,20 +42,19 @@
 //! ### Excluding Testcases
 //!
 //! NHI numbers that begin with `Z` are reserved for testing.
-//! If you wish to exclude these values using [is_nhi], you will need to manually check for a `Z`
-//! prefix:
+//! If you wish to exclude these values, use [is_nhi_excluding_test] or [NHI::parse_non_test]:
 //!
 //! ```rust
-//! use nhi::is_nhi;
+//! use nhi::{is_nhi, is_nhi_excluding_test, ParseNhiError, NHI};
 //!
 //! let value = "zvb97xq";
 //!
 //! assert_eq!(is_nhi(value),  true);
-//! assert_eq!(!value.to_uppercase().starts_with('Z') && is_nhi(value),  false);
-//!
+//! assert_eq!(is_nhi_excluding_test(value),  false);
+//! assert_eq!(NHI::parse_non_test(value), Err(ParseNhiError::ReservedForTesting));
 //! ```
 //!
-//! Alternatively, parsed [NHI] values provide [NHI::is_test] and [NHI::is_not_test] methods:
+//! Parsed [NHI] values also provide [NHI::is_test] and [NHI::is_not_test] methods:
 //!
 //! ```rust
 //! use nhi::NHI;
@@ -62,33 +69,160 @@
 //! ***Note:*** This check does not mean that the NHI number has been _assigned_ to
 //! a person, it just means that the NHI value is not reserved for testing.
 //!
+//! ## Features
+//!
+//! - `std` (default): implements `std::error::Error` for [ParseNhiError] and enables the helpers
+//!   that read and write `std::io` streams or use hash-based collections. Without it, this
+//!   crate is `no_std` and only requires `alloc`, so validating and parsing NHI numbers works
+//!   on embedded targets
+//! - `serde`: implements `Serialize` and `Deserialize` for [NHI], [NhiFormat] and
+//!   [NhiValidatedEvent], and `Serialize` for [ValidationReport] and [Severity]. Also enables
+//!   `NhiDetailed`, which serializes an NHI with its format and test status
+//! - `arbitrary`: implements `arbitrary::Arbitrary` for [NHI], generating only valid NHI
+//!   numbers of either format, for fuzzing
+//! - `proptest`: enables `nhi_strategy`, a `proptest` strategy generating only valid NHI numbers
+//!   of either format (implies `std`)
+//! - `clap`: implements `clap`'s `ValueParserFactory` for [NHI] with `NhiValueParser`, so
+//!   command line arguments can be parsed to NHIs directly (implies `std`)
+//! - `ffi`: exports the C functions `nhi_is_valid` and `nhi_format`, declared in
+//!   `include/nhi.h`, for validating NHI numbers from other languages
+//! - `json`: enables `validate_json_path` and `nhi_from_json_pointer` for validating NHI
+//!   numbers found in `serde_json` documents, and `JsonNhiStream` for validating large JSON
+//!   arrays as they are read (implies `serde`)
+//! - `pseudonym`: enables `NHI::pseudonym` and `NHI::pseudonym_hex`, which hash NHI numbers
+//!   into keyed HMAC-SHA256 tokens for joining datasets without handling the raw identifiers
+//! - `rand`: enables `shuffled_block` for generating non-sequential test data, and
+//!   `NHI::random`, `NHI::random_with_format` and `NHI::random_test` for generating random
+//!   valid NHI numbers
+//! - `research`: enables non-standard helpers for studying the NHI format, such as
+//!   `is_nhi_permissive_alphabet` and `is_nhi_custom_check`. These must never be used for real
+//!   validation
+//! - `sqlx`: implements `sqlx`'s `Type`, `Encode` and `Decode` for [NHI], storing NHIs as
+//!   text and validating every value read from the database (implies `std`)
+//! - `tracing`: emits a `tracing` debug event whenever a string fails to parse to an [NHI],
+//!   recording the reason and the string's length but never the string itself, for auditing
+//!   data quality
+//! - `wasm`: exports `is_nhi`, `parse_nhi` and `is_test_nhi` to JavaScript with `wasm-bindgen`,
+//!   for validating NHI numbers client-side
+//!
 //! ## See Also
 //!
 //! - <https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/>
 //! - <https://www.tewhatuora.govt.nz/our-health-system/digital-health/health-identity/national-health-index/information-for-health-it-vendors-and-developers>
 
-use std::fmt;
-use std::str::FromStr;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
-use lazy_static::lazy_static;
-use regex::Regex;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
 
 #[cfg(feature = "serde")]
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-lazy_static! {
-    static ref OLD_NHI_FORMAT: Regex = Regex::new(r"^[A-HJ-NP-Z]{3}\d{4}$").unwrap();
-    static ref NEW_NHI_FORMAT: Regex = Regex::new(r"^[A-HJ-NP-Z]{3}\d{2}[A-HJ-NP-Z]{2}$").unwrap();
-}
+mod batch;
+#[cfg(feature = "clap")]
+mod cli;
+#[cfg(feature = "serde")]
+mod detailed;
+mod enumerate;
+mod extract;
+#[cfg(feature = "ffi")]
+mod ffi;
+#[cfg(any(feature = "arbitrary", feature = "proptest"))]
+mod fuzzing;
+#[cfg(feature = "json")]
+mod json;
+mod normalize;
+mod packed;
+mod phonetic;
+mod production;
+mod pseudonym;
+#[cfg(feature = "rand")]
+mod random;
+mod recovery;
+mod registry;
+mod report;
+#[cfg(feature = "research")]
+mod research;
+mod set;
+mod sourced;
+#[cfg(feature = "sqlx")]
+mod sql;
+mod stats;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use batch::{bucket_by_format, find_duplicates, parse_list, partition_nhis, position_in, validate_all};
+#[cfg(feature = "std")]
+pub use batch::validate_reader;
+#[cfg(feature = "clap")]
+pub use cli::NhiValueParser;
+#[cfg(feature = "serde")]
+pub use detailed::NhiDetailed;
+pub use enumerate::checksum_collision;
+#[cfg(feature = "rand")]
+pub use enumerate::shuffled_block;
+pub use extract::{is_nhi_split, nhi_from_fixed_width, nhi_from_structured_qr, parse_split};
+#[cfg(feature = "ffi")]
+pub use ffi::{nhi_format, nhi_is_valid};
+#[cfg(feature = "proptest")]
+pub use fuzzing::nhi_strategy;
+#[cfg(feature = "json")]
+pub use json::{nhi_from_json_pointer, validate_json_path, JsonNhiStream, JsonStreamError};
+pub use normalize::{normalize_with_log, parse_note_formatting, InputFormatting, Normalization, NormalizationStep};
+#[cfg(feature = "std")]
+pub use packed::{read_packed_batch, write_packed_batch};
+pub use production::ProductionNhi;
+pub use recovery::{interpretations, likely_transcription_variants, recover_single_deletion, Interpretation};
+pub use registry::{is_assigned_nhi, Contains};
+pub use report::{validation_report, validation_severity, NhiValidatedEvent, Severity, ValidationReport};
+#[cfg(feature = "research")]
+pub use research::{is_nhi_custom_check, is_nhi_permissive_alphabet};
+pub use set::{difference, intersection, minimal_unique_prefix, symmetric_difference, NhiSet};
+pub use sourced::{parse_sourced, Sourced};
+pub use stats::{NhiStats, NhiSummary};
+#[cfg(feature = "wasm")]
+pub use wasm::{is_nhi_js, is_test_nhi, parse_nhi};
 
 /// Represents a valid NHI number that satisfies the
 /// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
 /// standard.
-#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-pub struct NHI(String);
+///
+/// With the `serde` feature, NHIs serialize as their underlying string and are validated as
+/// they are deserialized, so invalid strings fail to deserialize. Wrap an NHI in `NhiDetailed`
+/// to also serialize its format and test status.
+///
+/// ***Note:*** NHIs are sensitive health identifiers, so the `Debug` representation is
+/// redacted to the three-letter prefix (see [NHI::redacted]) to keep them out of logs and panic
+/// messages. Use [Display](fmt::Display) or [NHI::as_str] where the full value is genuinely
+/// needed.
+///
+/// NHIs are always stored in their canonical uppercase form, whichever case they were parsed
+/// from, so the derived [Eq], [Hash] and [Ord] are case-insensitive with respect to the input:
+/// `"zbn77vl"` and `"ZBN77VL"` parse to equal NHIs that hash identically.
+#[derive(Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct NHI(Cow<'static, str>);
 
 impl NHI {
+    /// Wraps a valid NHI string, uppercasing it in place
+    ///
+    /// Every owned NHI is constructed through here, so the stored string is always uppercase
+    /// and the derived `Eq` and `Hash` are consistent for differently-cased inputs.
+    /// [NHI::from_str_const] borrows its string instead, and rejects lowercase letters.
+    pub(crate) fn new_normalized(mut s: String) -> NHI {
+        s.make_ascii_uppercase();
+        debug_assert!(is_nhi(&s), "not a valid NHI");
+        NHI(Cow::Owned(s))
+    }
+
     /// Extracts a string slice containing this NHI number's underlying string value
     pub fn as_str(&self) -> &str {
         &self.0
@@ -96,216 +230,2180 @@ impl NHI {
 
     /// Converts this NHI to its underlying String value
     pub fn into_string(self) -> String {
-        self.0
-    }
-
-    /// Returns `true` if this NHI is reserved for testing and `false` otherwise
-    pub fn is_test(&self) -> bool {
-        self.0.starts_with('Z')
+        self.0.into_owned()
     }
 
-    /// Returns `true` if this NHI is NOT reserved for testing and `false` otherwise
-    pub fn is_not_test(&self) -> bool {
-        !self.0.starts_with('Z')
+    /// Creates an [NHI] from a string that is already known to be a valid NHI, e.g. one taken
+    /// from another [NHI], without running the NHI Validation Routine
+    ///
+    /// This is a performance escape hatch for hot loops, not a general constructor. Validity is
+    /// only checked by a `debug_assert!`, so in release builds an invalid string silently
+    /// produces an invalid [NHI] that breaks the guarantees of every other method, for example
+    /// by panicking or returning nonsense. Only use this where the caller can prove the string
+    /// came from a validated [NHI]. Use [NHI::from_str] for anything else, such as user input or
+    /// values read back from storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a valid NHI string
+    ///
+    /// returns: NHI
+    ///
+    /// # Panics
+    ///
+    /// In debug builds only, if `s` is not a valid NHI.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(NHI::from_trusted(nhi.as_str()), nhi);
+    /// ```
+    pub fn from_trusted(s: &str) -> NHI {
+        NHI::new_normalized(s.to_string())
     }
-}
 
-impl fmt::Display for NHI {
-    /// Formats this NHI as its underlying NHI value
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.0)
+    /// Parses ASCII bytes, e.g. a field of a fixed-width binary record, to an [NHI]
+    ///
+    /// NHIs are always ASCII, so no UTF-8 decoding is needed: the bytes are checked and
+    /// uppercased like [NHI::from_str].
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: a potential NHI
+    ///
+    /// returns: Result<NHI, ParseNhiError>, with [ParseNhiError::InvalidLength] if there are not
+    /// exactly seven bytes and [ParseNhiError::NonAscii] if any byte is not ASCII, with the
+    /// index of the first such byte
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{ParseNhiError, NHI};
+    ///
+    /// assert_eq!(NHI::from_bytes(b"zbn77vl").unwrap().as_str(), "ZBN77VL");
+    /// assert_eq!(NHI::from_bytes(b"ZBN77V\xFF"), Err(ParseNhiError::NonAscii { index: 6 }));
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Result<NHI, ParseNhiError> {
+        if bytes.len() != 7 {
+            return Err(ParseNhiError::InvalidLength);
+        }
+        if let Some(index) = bytes.iter().position(|b| !b.is_ascii()) {
+            return Err(ParseNhiError::NonAscii { index });
+        }
+        // ASCII is always valid UTF-8
+        core::str::from_utf8(bytes).unwrap().parse()
     }
-}
-
-/// Empty struct to indicate an invalid NHI string
-#[derive(Debug)]
-pub struct ParseNhiError;
 
-impl FromStr for NHI {
-    type Err = ParseNhiError;
-
-    /// Parses a string to an [NHI] iff the given string satisfies the
-    /// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
-    /// standard, otherwise returns an error.
+    /// Creates an [NHI] from a string in a `const` context, checking it against the NHI
+    /// Validation Routine at compile time
+    ///
+    /// Unlike [nhi!], this is a plain `const fn`, so it can initialise `const` and `static`
+    /// items. The string is borrowed rather than copied, so it must already be in its canonical
+    /// uppercase form.
     ///
     /// # Arguments
     ///
-    /// * `s`: a potential NHI string
+    /// * `s`: an uppercase NHI string
+    ///
+    /// returns: NHI
     ///
-    /// returns: Result<NHI, ParseNHIError>
+    /// # Panics
+    ///
+    /// Panics if the string is not a valid NHI or contains a lowercase letter. In a `const`
+    /// context this is a compile error.
     ///
     /// # Examples
     ///
     /// ```
     /// use nhi::NHI;
-    /// use std::str::FromStr;
     ///
-    /// let nhi = NHI::from_str("zbn77vl").unwrap();
-    /// assert_eq!(nhi.as_str(), "ZBN77VL")
+    /// const CLINIC: NHI = NHI::from_str_const("JBX3656");
+    /// assert_eq!(CLINIC.as_str(), "JBX3656");
     /// ```
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let nhi = s.to_uppercase();
-        if OLD_NHI_FORMAT.is_match(&nhi) {
-            let checksum = checksum(&nhi) % 11;
-            let check_digit = (11 - checksum) % 10;
-            if checksum != 0 && check_digit == char_code(nhi.chars().last().unwrap()) {
-                return Ok(NHI(nhi));
-            }
-        } else if NEW_NHI_FORMAT.is_match(&nhi) {
-            let checksum = checksum(&nhi) % 23;
-            let check_digit = 23 - checksum;
-            if check_digit == char_code(nhi.chars().last().unwrap()) {
-                return Ok(NHI(nhi));
+    pub const fn from_str_const(s: &'static str) -> NHI {
+        if !is_nhi(s) {
+            panic!("invalid NHI");
+        }
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i].is_ascii_lowercase() {
+                panic!("NHI must be uppercase");
             }
+            i += 1;
         }
-        Err(ParseNhiError)
+        NHI(Cow::Borrowed(s))
     }
-}
-
-/// Checks a string against the New Zealand Ministry of Health NHI specification
-/// defined by the
-/// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
-/// standard
-///
-/// # See Also
-/// <https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/>
-///
-/// # Arguments
-///
-/// * `nhi`: A potential NHI string
-///
-/// returns: `true` if the given string satisfies the New Zealand NHI Validation Routine and `false` otherwise
-///
-/// # Examples
-///
-/// ```
-/// use nhi::is_nhi;
-///
-/// assert_eq!(is_nhi("ZAC5361"), true);
-/// assert_eq!(is_nhi("ZBN77VL"), true);
-/// assert_eq!(is_nhi("ZZZ0044"), false);
-/// assert_eq!(is_nhi("ZZZ00AA"), false);
-/// ```
-pub fn is_nhi(nhi: &str) -> bool {
-    NHI::from_str(nhi).is_ok()
-}
-
-fn checksum(nhi: &str) -> u32 {
-    nhi.chars()
-        .enumerate()
-        .map(|(i, c)| char_code(c) * (7 - i as u32))
-        .take(6)
-        .sum::<u32>()
-}
 
-fn char_code(char: char) -> u32 {
-    if char.is_ascii_digit() {
-        char.to_digit(10).unwrap()
-    } else {
-        char as u32 - 64 - ('I' < char) as u32 - ('O' < char) as u32
+    /// Returns this NHI grouped for readability, e.g. for printing on wristbands and letters
+    ///
+    /// Both formats are grouped alike: the three-letter prefix, then the next two characters and
+    /// then the final two characters, separated by single spaces. This is also the alternate
+    /// [Display](fmt::Display) form, `{:#}`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "JBX3656".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.to_grouped(), "JBX 36 56");
+    /// assert_eq!(new.to_grouped(), "ZBN 77 VL");
+    /// assert_eq!(format!("{new:#}"), "ZBN 77 VL");
+    /// assert_eq!(format!("{new}"), "ZBN77VL");
+    /// ```
+    pub fn to_grouped(&self) -> String {
+        format!("{self:#}")
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Compares this NHI to another in constant time
+    ///
+    /// `==` stops at the first differing character, so how long a comparison takes can reveal
+    /// how much of a guessed NHI is correct. Prefer this where an NHI gates access and a caller
+    /// may time repeated guesses against a secret value, e.g. when checking a submitted NHI
+    /// against the one on record. Every NHI is seven ASCII characters, so all seven are always
+    /// compared.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let on_record: NHI = "ZBN77VL".parse().unwrap();
+    /// assert!(on_record.ct_eq(&"zbn77vl".parse().unwrap()));
+    /// assert!(!on_record.ct_eq(&"ZAC5361".parse().unwrap()));
+    /// ```
+    pub fn ct_eq(&self, other: &NHI) -> bool {
+        let difference = self.0.bytes().zip(other.0.bytes()).fold(0, |acc, (a, b)| acc | (a ^ b));
+        core::hint::black_box(difference) == 0
+    }
 
-    const VALID_OLD: [&str; 15] = [
-        "JBX3656", "ZZZ0016", "ZZZ0024", "ZAA0067", "ZAA0075", "ZAA0083", "ZAA0091",
-        "ZAA0105", "ZAA0113", "ZAA0121", "ZAA0130", "ZAA0148", "ZAA0156", "ZAC5361",
-        "ABC1235",
-    ];
-    const VALID_NEW: [&str; 11] = [
-        "ZBN77VL", "ZZZ00AC", "ZDR69YX", "ZSC21TN", "ZZB30NH", "ZYZ81ZV", "ZVB97XQ",
-        "ZRA29VA", "ZYX61YS", "ABC12AY", "XYZ12AN",
-    ];
-    const INVALID_OLD: [&str; 3] = ["ZZZ0044", "ZZZ0017", "DAB8233"];
-    const INVALID_NEW: [&str; 4] = ["ZZZ00AA", "ZZZ00AY", "ZVU27KY", "ZVU27KA"];
-    const RANDOM_STRINGS: [&str; 7] = [
-        "not an NHI", "!@#$%&*", "AAANNNC", "AAANNAC", "ZVU27K", "JBX365", "",
-    ];
+    /// Returns this NHI with every character after the three-letter prefix replaced by `•`, e.g.
+    /// `"ZBN••••"`, for logging without revealing the full value
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(nhi.redacted(), "ZBN••••");
+    /// assert_eq!(format!("{nhi:?}"), r#"NHI("ZBN••••")"#);
+    /// assert_eq!(nhi.to_string(), "ZBN77VL");
+    /// ```
+    pub fn redacted(&self) -> String {
+        format!("{}••••", &self.0[..3])
+    }
 
-    #[test]
-    fn is_nhi_recognises_valid_old_format_nhi_numbers() {
-        for nhi in VALID_OLD {
-            assert!(is_nhi(nhi));
-        }
+    /// Returns a key suitable for case-insensitive database indexing
+    ///
+    /// The key is guaranteed to always be the canonical uppercase form of this NHI, regardless of
+    /// how it was parsed or constructed, and to remain so in future versions of this crate even
+    /// if the internal representation changes. Equal NHIs always have equal index keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "zbn77vl".parse().unwrap();
+    /// assert_eq!(nhi.index_key(), "ZBN77VL");
+    /// ```
+    pub fn index_key(&self) -> &str {
+        &self.0
     }
 
-    #[test]
-    fn is_nhi_rejects_invalid_old_format_nhi_numbers() {
-        for nhi in INVALID_OLD {
-            assert!(!is_nhi(nhi));
-        }
-        // Needs a check digit of 6
-        for i in 0..10 {
-            if i != 6 {
-                assert!(!is_nhi(&format!("JBX365{i}")));
-            }
-        }
+    /// Returns the purpose this NHI is reserved for, if any, see [Reservation]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NHI, Reservation};
+    ///
+    /// let reserved: NHI = "ZAA0105".parse().unwrap();
+    /// let unreserved: NHI = "JBX3656".parse().unwrap();
+    /// assert_eq!(reserved.reservation(), Reservation::Test);
+    /// assert_eq!(unreserved.reservation(), Reservation::None);
+    /// ```
+    pub fn reservation(&self) -> Reservation {
+        if self.0.starts_with('Z') { Reservation::Test } else { Reservation::None }
     }
 
-    #[test]
-    fn no_digit_can_be_added_to_an_old_format_nhi_with_a_checksum_of_0_to_make_it_valid() {
-        for i in 0..10 {
-            assert!(!is_nhi(&format!("ZZZ004{i}")));
-        }
+    /// Returns `true` if this NHI is reserved for testing and `false` otherwise
+    pub fn is_test(&self) -> bool {
+        self.reservation() == Reservation::Test
     }
 
-    #[test]
-    fn is_nhi_recognises_valid_new_format_nhi_numbers() {
-        for nhi in VALID_NEW {
-            assert!(is_nhi(nhi));
-        }
+    /// Returns `true` if this NHI is NOT reserved for testing and `false` otherwise
+    pub fn is_not_test(&self) -> bool {
+        !self.is_test()
     }
 
-    #[test]
-    fn is_nhi_rejects_invalid_new_format_nhi_numbers() {
-        for nhi in INVALID_NEW {
-            assert!(!is_nhi(nhi));
-        }
-        // Needs a check character of V
-        for c in "ABCDEFGHJKLMNPQRSTUWXYZ".chars() {
-            assert!(!is_nhi(&format!("ZHW58C{c}")));
-        }
+    /// Parses a string to an [NHI] that is not reserved for testing
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential NHI string
+    ///
+    /// returns: Result<NHI, ParseNhiError>, with the same errors as [NHI::from_str], or
+    /// [ParseNhiError::ReservedForTesting] if the string is a valid NHI beginning with `Z`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{ParseNhiError, NHI};
+    ///
+    /// assert_eq!(NHI::parse_non_test("jbx3656").unwrap().as_str(), "JBX3656");
+    /// assert_eq!(NHI::parse_non_test("ZBN77VL"), Err(ParseNhiError::ReservedForTesting));
+    /// ```
+    pub fn parse_non_test(s: &str) -> Result<NHI, ParseNhiError> {
+        let nhi: NHI = s.parse()?;
+        if nhi.is_test() { Err(ParseNhiError::ReservedForTesting) } else { Ok(nhi) }
     }
 
-    #[test]
-    fn is_nhi_rejects_random_strings() {
-        for nhi in RANDOM_STRINGS {
-            assert!(!is_nhi(nhi));
-        }
+    /// Returns `true` if this NHI is reserved for testing or starts with any of the given extra
+    /// prefixes, and `false` otherwise
+    ///
+    /// The standard `Z` test reservation always applies in addition to the extra prefixes, which
+    /// are matched case-insensitively. Empty prefixes are ignored rather than matching every NHI.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let reserved: NHI = "ZAA0105".parse().unwrap();
+    /// let synthetic: NHI = "XYZ12AN".parse().unwrap();
+    /// let unreserved: NHI = "JBX3656".parse().unwrap();
+    /// assert!(reserved.is_test_with(&["xy"]));
+    /// assert!(synthetic.is_test_with(&["xy"]));
+    /// assert!(!unreserved.is_test_with(&["xy"]));
+    /// ```
+    pub fn is_test_with(&self, extra: &[&str]) -> bool {
+        self.is_test() || extra.iter().any(|prefix| {
+            !prefix.is_empty() && self.0.starts_with(&prefix.to_ascii_uppercase())
+        })
     }
 
-    #[test]
-    fn is_nhi_is_case_insensitive() {
-        for nhi in VALID_OLD.iter().chain(VALID_NEW.iter()) {
-            assert!(is_nhi(&nhi.to_lowercase()));
-        }
-        for nhi in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
-            assert!(!is_nhi(&nhi.to_lowercase()));
-        }
+    /// Returns the label of the test environment this NHI is reserved for, or `None` if it is
+    /// not reserved for testing
+    ///
+    /// The default mapping only recognises the generic `Z` test reservation, which is labelled
+    /// `"test"`. Use [NHI::test_environment_with] to recognise organisation-specific sub-ranges.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let reserved: NHI = "ZAA0105".parse().unwrap();
+    /// let unreserved: NHI = "JBX3656".parse().unwrap();
+    /// assert_eq!(reserved.test_environment(), Some("test"));
+    /// assert_eq!(unreserved.test_environment(), None);
+    /// ```
+    pub fn test_environment(&self) -> Option<&'static str> {
+        self.is_test().then_some(TestRangeMap::DEFAULT_LABEL)
     }
 
-    #[test]
-    fn nhi_numbers_can_be_parsed_from_strings_to_results() {
-        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
-            assert!(NHI::from_str(nhi_str).is_ok());
-        }
-        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
-            assert!(NHI::from_str(nhi_str).is_err());
+    /// Returns the label of the test environment this NHI is reserved for according to the given
+    /// mapping, or `None` if it is not reserved for testing
+    ///
+    /// The longest prefix in the mapping that matches this NHI determines its label. Test NHIs
+    /// that match no prefix in the mapping are labelled `"test"`. Prefixes outside the `Z` test
+    /// space are never matched.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NHI, TestRangeMap};
+    ///
+    /// let mut map = TestRangeMap::new();
+    /// map.insert("ZA", "staging");
+    /// map.insert("ZAC", "training");
+    ///
+    /// let staging: NHI = "ZAA0105".parse().unwrap();
+    /// let training: NHI = "ZAC5361".parse().unwrap();
+    /// let generic: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(staging.test_environment_with(&map), Some("staging"));
+    /// assert_eq!(training.test_environment_with(&map), Some("training"));
+    /// assert_eq!(generic.test_environment_with(&map), Some("test"));
+    /// ```
+    pub fn test_environment_with<'a>(&self, map: &'a TestRangeMap) -> Option<&'a str> {
+        if self.is_not_test() {
+            return None;
         }
+        let label = map.ranges.iter()
+            .filter(|(prefix, _)| self.0.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(TestRangeMap::DEFAULT_LABEL, |(_, label)| label.as_str());
+        Some(label)
     }
 
-    #[test]
-    fn nhi_numbers_can_be_converted_to_strings() {
-        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+    /// Returns the zero-based position of this NHI's three-letter prefix in the ordering of all
+    /// legal prefixes (`AAA` is `0`, `AAB` is `1`, ... `ZZZ` is `13823`).
+    ///
+    /// Prefixes have historically been allocated roughly alphabetically, so this can serve as a
+    /// rough recency heuristic for sorting or bucketing.
+    ///
+    /// ***Note:*** This is a purely structural ordinal derived from the letters of the prefix.
+    /// It is _not_ an official issuance date and makes no guarantee about when an NHI was
+    /// actually allocated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ABC1235".parse().unwrap();
+    /// assert_eq!(nhi.prefix_ordinal(), 26);
+    /// ```
+    pub fn prefix_ordinal(&self) -> u32 {
+        self.0
+            .chars()
+            .take(3)
+            .fold(0, |acc, c| acc * 24 + char_code(c).unwrap() - 1)
+    }
+
+    /// Returns this NHI's check value for display: the check digit of an old format NHI or the
+    /// check character of a new format NHI
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "ZAC5361".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.check_display(), "1");
+    /// assert_eq!(new.check_display(), "L");
+    /// ```
+    pub fn check_display(&self) -> String {
+        self.check_char().to_string()
+    }
+
+    /// Returns the check character of a new format NHI, or `None` for an old format NHI
+    ///
+    /// Old format check digits are computed modulo 11 and new format check characters modulo
+    /// 23, so there is no meaningful mapping from an old format check digit into the letter
+    /// space. Old format NHIs therefore always return `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "ZAC5361".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.check_as_letter(), None);
+    /// assert_eq!(new.check_as_letter(), Some('L'));
+    /// ```
+    pub fn check_as_letter(&self) -> Option<char> {
+        Some(self.check_char()).filter(char::is_ascii_alphabetic)
+    }
+
+    /// Returns the format of this NHI, i.e. whether it ends in a check digit or a check
+    /// character
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// let old: NHI = "ZAC5361".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.format(), NhiFormat::Old);
+    /// assert_eq!(new.format(), NhiFormat::New);
+    /// ```
+    pub fn format(&self) -> NhiFormat {
+        if self.check_char().is_ascii_alphabetic() { NhiFormat::New } else { NhiFormat::Old }
+    }
+
+    /// Returns the format whose character pattern a string has, without checking its check
+    /// digit or check character
+    ///
+    /// Only the length and the letter and digit positions are checked, case-insensitively, so
+    /// this suits giving feedback on a value's shape as it is typed. Use [NHI::from_str] or
+    /// [is_nhi] for the full check. See also [old_format_positions_valid] and
+    /// [new_format_positions_valid].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// assert_eq!(NHI::matches_format("zzz0044"), Some(NhiFormat::Old));
+    /// assert_eq!(NHI::matches_format("ZBN77VK"), Some(NhiFormat::New));
+    /// assert_eq!(NHI::matches_format("ZBN77V"), None);
+    /// assert!("ZZZ0044".parse::<NHI>().is_err());
+    /// ```
+    pub fn matches_format(s: &str) -> Option<NhiFormat> {
+        if old_format_positions_valid(s) {
+            Some(NhiFormat::Old)
+        } else if new_format_positions_valid(s) {
+            Some(NhiFormat::New)
+        } else {
+            None
+        }
+    }
+
+    /// Returns a stable code for this NHI's format, `"OLD"` or `"NEW"`, for use as a log or
+    /// metrics label
+    ///
+    /// These codes are part of this crate's stable API and, unlike the `Debug` representation
+    /// of [NhiFormat], will never change.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "ZAC5361".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.format_code(), "OLD");
+    /// assert_eq!(new.format_code(), "NEW");
+    /// ```
+    pub fn format_code(&self) -> &'static str {
+        match self.format() {
+            NhiFormat::Old => "OLD",
+            NhiFormat::New => "NEW",
+        }
+    }
+
+    /// Returns the four digits following the prefix of an old format NHI, including its check
+    /// digit, or `None` for a new format NHI
+    ///
+    /// Leading zeros are part of the returned string, so it is always four characters long and
+    /// suits fixed-width numeric storage in legacy systems.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "ZAA0105".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.old_numeric_body(), Some("0105"));
+    /// assert_eq!(new.old_numeric_body(), None);
+    /// ```
+    pub fn old_numeric_body(&self) -> Option<&str> {
+        (self.format() == NhiFormat::Old).then(|| &self.0[3..])
+    }
+
+    /// Returns the checksum the NHI Validation Routine computes for this NHI: the weighted sum of
+    /// its first six characters (see [checksum_contributions]) modulo 11 for the old format, or
+    /// modulo 23 for the new format
+    ///
+    /// The check digit or check character is derived from this value, see [compute_check_char].
+    /// The checksum of a valid old format NHI is never `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let old: NHI = "ZAC5361".parse().unwrap();
+    /// let new: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(old.checksum(), 230 % 11);
+    /// assert_eq!(new.checksum(), 334 % 23);
+    /// ```
+    pub fn checksum(&self) -> u32 {
+        let sum = checksum(&self.0).unwrap();
+        match self.format() {
+            NhiFormat::Old => sum % 11,
+            NhiFormat::New => sum % 23,
+        }
+    }
+
+    fn check_char(&self) -> char {
+        self.0.chars().last().unwrap()
+    }
+}
+
+/// The NHI number formats defined by the
+/// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
+/// standard
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum NhiFormat {
+    /// Three letters (excluding `I` and `O`) followed by four digits, the last being a check
+    /// digit
+    Old,
+    /// Three letters, two digits and then two letters (excluding `I` and `O`), the last being a
+    /// check character
+    New,
+}
+
+/// The purposes an NHI can be reserved for by the
+/// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
+/// standard, see [NHI::reservation]
+///
+/// The standard currently reserves only NHIs beginning with `Z`, for testing. Further
+/// categories may be added as the standard documents them, so matches should include a
+/// wildcard arm. Organisation-specific test sub-ranges are not reservations of the standard,
+/// see [NHI::is_test_with] and [NHI::test_environment_with] for those.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Reservation {
+    /// The NHI is not reserved, so may be assigned to a real person
+    None,
+    /// The NHI begins with `Z`, which HISO 10046 reserves for testing, so is never assigned to
+    /// a real person
+    Test,
+}
+
+/// A version of the HISO 10046 Consumer Health Identity Standard defining the NHI Validation
+/// Routine
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+#[non_exhaustive]
+pub enum HisoVersion {
+    /// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/),
+    /// which this crate validates against by default
+    #[default]
+    V2023,
+}
+
+/// A mapping from sub-ranges of the `Z` test space to test environment labels, used by
+/// [NHI::test_environment_with]
+///
+/// Sub-ranges are identified by NHI prefixes (e.g. `"ZA"` or `"ZAC"`), matched case-insensitively.
+#[derive(Debug, Clone, Default)]
+pub struct TestRangeMap {
+    ranges: Vec<(String, String)>,
+}
+
+impl TestRangeMap {
+    const DEFAULT_LABEL: &'static str = "test";
+
+    /// Creates an empty mapping, which only recognises the generic `Z` test reservation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Labels all test NHIs starting with `prefix` with the given environment label, replacing
+    /// any previous label for the same prefix
+    pub fn insert(&mut self, prefix: &str, label: impl Into<String>) {
+        let prefix = prefix.to_ascii_uppercase();
+        let label = label.into();
+        match self.ranges.iter_mut().find(|(p, _)| *p == prefix) {
+            Some(entry) => entry.1 = label,
+            None => self.ranges.push((prefix, label)),
+        }
+    }
+}
+
+impl fmt::Display for NHI {
+    /// Formats this NHI as its underlying NHI value, or grouped for readability with the
+    /// alternate flag (`{:#}`), see [NHI::to_grouped]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            write!(f, "{} {} {}", &self.0[..3], &self.0[3..5], &self.0[5..])
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl AsRef<str> for NHI {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for NHI {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl From<NHI> for String {
+    /// Converts an NHI to its underlying String value, see [NHI::into_string]
+    fn from(nhi: NHI) -> Self {
+        nhi.into_string()
+    }
+}
+
+impl From<&NHI> for String {
+    fn from(nhi: &NHI) -> Self {
+        nhi.as_str().to_string()
+    }
+}
+
+impl Borrow<str> for NHI {
+    /// Borrows this NHI as its canonical uppercase string, so collections of NHIs can be
+    /// queried with uppercase `&str` keys
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for NHI {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Compares an [NHI] to a string case-insensitively, so `nhi == "zbn77vl"` holds for the NHI
+/// `ZBN77VL`
+///
+/// ***Note:*** Unlike comparing two [NHI]s, or an NHI's [NHI::as_str] to a string, this ignores
+/// case. The string is not validated, so an NHI is never equal to an invalid string.
+impl PartialEq<str> for NHI {
+    fn eq(&self, other: &str) -> bool {
+        self.0.eq_ignore_ascii_case(other)
+    }
+}
+
+impl PartialEq<&str> for NHI {
+    /// Compares case-insensitively, see [NHI]'s `PartialEq<str>`
+    fn eq(&self, other: &&str) -> bool {
+        *self == **other
+    }
+}
+
+impl PartialEq<String> for NHI {
+    /// Compares case-insensitively, see [NHI]'s `PartialEq<str>`
+    fn eq(&self, other: &String) -> bool {
+        *self == *other.as_str()
+    }
+}
+
+impl PartialEq<NHI> for str {
+    /// Compares case-insensitively, see [NHI]'s `PartialEq<str>`
+    fn eq(&self, other: &NHI) -> bool {
+        *other == *self
+    }
+}
+
+impl PartialEq<NHI> for &str {
+    /// Compares case-insensitively, see [NHI]'s `PartialEq<str>`
+    fn eq(&self, other: &NHI) -> bool {
+        *other == **self
+    }
+}
+
+impl PartialEq<NHI> for String {
+    /// Compares case-insensitively, see [NHI]'s `PartialEq<str>`
+    fn eq(&self, other: &NHI) -> bool {
+        *other == *self.as_str()
+    }
+}
+
+impl fmt::Debug for NHI {
+    /// Formats this NHI with all but its prefix redacted, e.g. `NHI("ZBN••••")`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("NHI").field(&self.redacted()).finish()
+    }
+}
+
+impl NHI {
+    /// Returns a value that displays this NHI with [TestTagged::MARKER] appended if it is
+    /// reserved for testing
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let reserved: NHI = "ZAA0105".parse().unwrap();
+    /// let unreserved: NHI = "JBX3656".parse().unwrap();
+    /// assert_eq!(reserved.test_tagged().to_string(), "ZAA0105 (TEST)");
+    /// assert_eq!(unreserved.test_tagged().to_string(), "JBX3656");
+    /// ```
+    pub fn test_tagged(&self) -> TestTagged<'_> {
+        TestTagged(self)
+    }
+}
+
+/// Displays an [NHI], flagging values reserved for testing, see [NHI::test_tagged]
+#[derive(Debug, Clone, Copy)]
+pub struct TestTagged<'a>(&'a NHI);
+
+impl TestTagged<'_> {
+    /// The exact marker appended to NHIs reserved for testing
+    pub const MARKER: &'static str = " (TEST)";
+}
+
+impl fmt::Display for TestTagged<'_> {
+    /// Formats the NHI as its underlying value, followed by [TestTagged::MARKER] if it is
+    /// reserved for testing
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_test() {
+            write!(f, "{}{}", self.0, Self::MARKER)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+impl NHI {
+    /// Returns this NHI's value, format and test status together, borrowing from the NHI
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// let nhi: NHI = "zbn77vl".parse().unwrap();
+    /// let info = nhi.info();
+    /// assert_eq!(info.value, "ZBN77VL");
+    /// assert_eq!(info.format, NhiFormat::New);
+    /// assert!(info.is_test);
+    /// ```
+    pub fn info(&self) -> NhiInfo<'_> {
+        NhiInfo { value: self.as_str(), format: self.format(), is_test: self.is_test() }
+    }
+}
+
+/// An [NHI]'s value, format and test status, see [NHI::info]
+///
+/// ***Note:*** Unlike [NHI]'s, this `Debug` representation includes the full value. Use
+/// [NHI::redacted] for logs.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct NhiInfo<'a> {
+    /// The NHI, in its canonical uppercase form, see [NHI::as_str]
+    pub value: &'a str,
+    /// The format of the NHI, see [NHI::format]
+    pub format: NhiFormat,
+    /// Whether the NHI is reserved for testing, see [NHI::is_test]
+    pub is_test: bool,
+}
+
+/// The reason a string is not a valid NHI
+///
+/// Each variant corresponds to one of the rules of the NHI Validation Routine that the string
+/// breaks, see [ParseNhiError::rule].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ParseNhiError {
+    /// The string is not seven characters long
+    InvalidLength,
+    /// The string has the right length but does not match the character pattern of either
+    /// format
+    ///
+    /// Parsing a whole NHI reports the more specific [ParseNhiError::ForbiddenLetter] or
+    /// [ParseNhiError::UnexpectedCharacter] instead, so this comes from functions checking only
+    /// part of an NHI, such as [compute_check_char].
+    InvalidFormat,
+    /// The string contains a non-ASCII character, such as an accented or full-width letter,
+    /// which NHIs never contain
+    NonAscii {
+        /// The index of the first non-ASCII character, in characters
+        index: usize,
+    },
+    /// The string contains the letter `I` or `O`, which NHIs never contain as they are easily
+    /// mistaken for `1` and `0`
+    ForbiddenLetter {
+        /// The forbidden letter, in uppercase
+        letter: char,
+        /// The index of the first forbidden letter, in characters
+        index: usize,
+    },
+    /// The string has a character not of the class expected at its position, e.g. a letter
+    /// where a digit is expected, or punctuation anywhere
+    ///
+    /// This is the first such position, e.g. index `3` for a string of only letters, as both
+    /// formats have digits at positions 4 and 5 (and the old format at 6 and 7 too). The sixth
+    /// character may be a letter or digit, and which the final character should be follows from
+    /// it.
+    UnexpectedCharacter {
+        /// The index of the first character of the wrong class
+        index: usize,
+        /// The class of character expected at the index
+        expected: CharacterClass,
+    },
+    /// The string matches a format but its check digit or check character is wrong
+    ChecksumMismatch {
+        /// The correct check digit or check character, in uppercase
+        expected: char,
+        /// The final character of the string, in uppercase
+        found: char,
+    },
+    /// The string matches the old format but no check digit is valid for its first six
+    /// characters, so it can never be a valid NHI
+    NoValidCheckDigit,
+    /// The string is a valid NHI but begins with `Z`, so it is reserved for testing. Only
+    /// returned where test NHIs are excluded, see [NHI::parse_non_test]
+    ReservedForTesting,
+}
+
+/// A class of character found in NHIs, see [ParseNhiError::UnexpectedCharacter]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum CharacterClass {
+    /// A letter, `A` to `Z` excluding `I` and `O`
+    Letter,
+    /// A digit, `0` to `9`
+    Digit,
+    /// Either a letter or a digit
+    Alphanumeric,
+}
+
+impl fmt::Display for CharacterClass {
+    /// Formats this class as a lowercase noun, e.g. `letter`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CharacterClass::Letter => write!(f, "letter"),
+            CharacterClass::Digit => write!(f, "digit"),
+            CharacterClass::Alphanumeric => write!(f, "letter or digit"),
+        }
+    }
+}
+
+impl fmt::Display for ParseNhiError {
+    /// Formats this error as a human-readable description of the rule broken
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid NHI: ")?;
+        match self {
+            ParseNhiError::InvalidLength => write!(f, "must be exactly seven characters long"),
+            ParseNhiError::InvalidFormat => {
+                write!(f, "does not match the old or new format character pattern")
+            }
+            ParseNhiError::NonAscii { index } => {
+                write!(f, "contains a non-ASCII character at index {index}")
+            }
+            ParseNhiError::ForbiddenLetter { letter, index } => {
+                write!(f, "contains the forbidden letter `{letter}` at index {index}")
+            }
+            ParseNhiError::UnexpectedCharacter { index, expected } => {
+                write!(f, "expected a {expected} at index {index}")
+            }
+            ParseNhiError::ChecksumMismatch { expected, found } => {
+                write!(f, "expected check character `{expected}` but found `{found}`")
+            }
+            ParseNhiError::NoValidCheckDigit => {
+                write!(f, "no check digit is valid for the first six characters")
+            }
+            ParseNhiError::ReservedForTesting => {
+                write!(f, "begins with `Z` so is reserved for testing")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseNhiError {}
+
+/// A rule of the
+/// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
+/// NHI Validation Routine
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    /// An NHI is exactly seven characters long
+    Length,
+    /// An NHI is three letters (excluding `I` and `O`), followed by either four digits (the old
+    /// format) or two digits and two letters (the new format)
+    CharacterSet,
+    /// The final character of an NHI is the check digit (old format) or check character (new
+    /// format) computed from the first six characters
+    CheckDigit,
+    /// An NHI beginning with `Z` is reserved for testing. This rule is only enforced where test
+    /// NHIs are excluded, see [NHI::parse_non_test]
+    TestPrefix,
+}
+
+impl ParseNhiError {
+    /// Returns the validation rule this error breaks
+    ///
+    /// | Variant                              | Rule                  |
+    /// |--------------------------------------|-----------------------|
+    /// | [ParseNhiError::InvalidLength]       | [Rule::Length]        |
+    /// | [ParseNhiError::InvalidFormat]       | [Rule::CharacterSet]  |
+    /// | [ParseNhiError::NonAscii]            | [Rule::CharacterSet]  |
+    /// | [ParseNhiError::ForbiddenLetter]     | [Rule::CharacterSet]  |
+    /// | [ParseNhiError::UnexpectedCharacter] | [Rule::CharacterSet]  |
+    /// | [ParseNhiError::ChecksumMismatch]    | [Rule::CheckDigit]    |
+    /// | [ParseNhiError::NoValidCheckDigit]   | [Rule::CheckDigit]    |
+    /// | [ParseNhiError::ReservedForTesting]  | [Rule::TestPrefix]    |
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NHI, Rule};
+    ///
+    /// let error = "ZZZ0044".parse::<NHI>().unwrap_err();
+    /// assert_eq!(error.rule(), Rule::CheckDigit);
+    /// ```
+    pub fn rule(&self) -> Rule {
+        match self {
+            ParseNhiError::InvalidLength => Rule::Length,
+            ParseNhiError::InvalidFormat
+            | ParseNhiError::NonAscii { .. }
+            | ParseNhiError::ForbiddenLetter { .. }
+            | ParseNhiError::UnexpectedCharacter { .. } => Rule::CharacterSet,
+            ParseNhiError::ChecksumMismatch { .. } | ParseNhiError::NoValidCheckDigit => Rule::CheckDigit,
+            ParseNhiError::ReservedForTesting => Rule::TestPrefix,
+        }
+    }
+}
+
+impl ParseNhiError {
+    /// Returns the name of this error's variant, which unlike its `Debug` representation never
+    /// includes characters of the string
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ParseNhiError::InvalidLength => "InvalidLength",
+            ParseNhiError::InvalidFormat => "InvalidFormat",
+            ParseNhiError::NonAscii { .. } => "NonAscii",
+            ParseNhiError::ForbiddenLetter { .. } => "ForbiddenLetter",
+            ParseNhiError::UnexpectedCharacter { .. } => "UnexpectedCharacter",
+            ParseNhiError::ChecksumMismatch { .. } => "ChecksumMismatch",
+            ParseNhiError::NoValidCheckDigit => "NoValidCheckDigit",
+            ParseNhiError::ReservedForTesting => "ReservedForTesting",
+        }
+    }
+}
+
+impl FromStr for NHI {
+    type Err = ParseNhiError;
+
+    /// Parses a string to an [NHI] iff the given string satisfies the
+    /// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
+    /// standard, otherwise returns the most specific error that describes why it does not.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential NHI string
+    ///
+    /// returns: Result<NHI, ParseNhiError>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    /// use std::str::FromStr;
+    ///
+    /// let nhi = NHI::from_str("zbn77vl").unwrap();
+    /// assert_eq!(nhi.as_str(), "ZBN77VL")
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let nhi = validate_uppercase(s).inspect_err(|error| trace_invalid(s, error))?;
+        Ok(NHI::new_normalized(core::str::from_utf8(&nhi).unwrap().to_string()))
+    }
+}
+
+impl TryFrom<&str> for NHI {
+    type Error = ParseNhiError;
+
+    /// Parses a string to an [NHI], see [NHI::from_str]
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl TryFrom<String> for NHI {
+    type Error = ParseNhiError;
+
+    /// Parses an owned string to an [NHI], see [NHI::from_str]
+    ///
+    /// A valid string is uppercased in place and moved into the [NHI], so this never allocates.
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        validate_uppercase(&s).inspect_err(|error| trace_invalid(&s, error))?;
+        Ok(NHI::new_normalized(s))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for NHI {
+    /// Deserializes a string and parses it to an [NHI], failing if it is not a valid NHI
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        NHI::try_from(String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Checks a string against the New Zealand Ministry of Health NHI specification
+/// defined by the
+/// [HISO 10046:2023](https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/)
+/// standard
+///
+/// # See Also
+/// <https://www.tewhatuora.govt.nz/publications/hiso-100462023-consumer-health-identity-standard/>
+///
+/// # Arguments
+///
+/// * `nhi`: A potential NHI string
+///
+/// returns: `true` if the given string satisfies the New Zealand NHI Validation Routine and `false` otherwise
+///
+/// This is a `const fn`, so strings can also be checked at compile time, see [nhi!].
+///
+/// # Examples
+///
+/// ```
+/// use nhi::is_nhi;
+///
+/// assert_eq!(is_nhi("ZAC5361"), true);
+/// assert_eq!(is_nhi("ZBN77VL"), true);
+/// assert_eq!(is_nhi("ZZZ0044"), false);
+/// assert_eq!(is_nhi("ZZZ00AA"), false);
+///
+/// const VALID: bool = is_nhi("zbn77vl");
+/// assert!(VALID);
+/// ```
+pub const fn is_nhi(nhi: &str) -> bool {
+    validate_uppercase(nhi).is_ok()
+}
+
+/// Checks a string is a valid NHI that is not reserved for testing (i.e. does not begin with
+/// `Z`)
+///
+/// This is a `const fn`, like [is_nhi].
+///
+/// # Examples
+///
+/// ```
+/// use nhi::is_nhi_excluding_test;
+///
+/// assert_eq!(is_nhi_excluding_test("jbx3656"), true);
+/// assert_eq!(is_nhi_excluding_test("ZBN77VL"), false);
+/// assert_eq!(is_nhi_excluding_test("JBX3657"), false);
+/// ```
+pub const fn is_nhi_excluding_test(nhi: &str) -> bool {
+    is_nhi(nhi) && !matches!(nhi.as_bytes()[0], b'Z' | b'z')
+}
+
+/// Validates a string against the NHI Validation Routine, returning the string itself when it
+/// is valid
+///
+/// Unlike [NHI::from_str], no owned [NHI] is constructed and nothing is allocated, so this suits
+/// scanning larger text while keeping borrows of the original. ***Note:*** the returned slice
+/// is the caller's input, untouched, so it keeps its original casing and is not normalized to
+/// uppercase. Use [NHI::from_str] for the canonical form.
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI string
+///
+/// returns: Result<&str, ParseNhiError>, the same errors as [NHI::from_str]
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{validate, ParseNhiError};
+///
+/// assert_eq!(validate("zBn77vL"), Ok("zBn77vL"));
+/// assert_eq!(validate("ZZZ0044"), Err(ParseNhiError::NoValidCheckDigit));
+/// ```
+pub fn validate(s: &str) -> Result<&str, ParseNhiError> {
+    validate_uppercase(s).map(|_| s)
+}
+
+/// Creates an [NHI] from a string literal that is checked against the NHI Validation Routine
+/// at compile time
+///
+/// The literal is case-insensitive. An invalid literal, including one with a wrong check digit
+/// or check character, is a compile error rather than a runtime panic. The macro expands to a
+/// block that asserts [is_nhi] on the literal in a constant and then parses it:
+///
+/// ```
+/// # use nhi::NHI;
+/// # let _ =
+/// {
+///     const _: () = assert!(nhi::is_nhi("zbn77vl"), "invalid NHI literal: zbn77vl");
+///     "zbn77vl".parse::<NHI>().unwrap()
+/// }
+/// # ;
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// use nhi::nhi;
+///
+/// let nhi = nhi!("zbn77vl");
+/// assert_eq!(nhi.as_str(), "ZBN77VL");
+/// ```
+///
+/// ```compile_fail
+/// use nhi::nhi;
+///
+/// let nhi = nhi!("ZZZ0044");
+/// ```
+#[macro_export]
+macro_rules! nhi {
+    ($nhi:literal) => {{
+        const _: () = ::core::assert!(
+            $crate::is_nhi($nhi),
+            ::core::concat!("invalid NHI literal: ", $nhi),
+        );
+        <$crate::NHI as ::core::str::FromStr>::from_str($nhi).unwrap()
+    }};
+}
+
+/// Checks a string with [is_nhi], also returning the version of the standard it was checked
+/// against
+///
+/// The version is this crate's default [HisoVersion]. Validity under a fixed version never
+/// changes, so cached results need only be revalidated when the returned version changes, e.g.
+/// after upgrading this crate.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{is_nhi_versioned, HisoVersion};
+///
+/// assert_eq!(is_nhi_versioned("ZBN77VL"), (true, HisoVersion::V2023));
+/// assert_eq!(is_nhi_versioned("ZZZ0044"), (false, HisoVersion::V2023));
+/// ```
+pub fn is_nhi_versioned(s: &str) -> (bool, HisoVersion) {
+    (is_nhi(s), HisoVersion::default())
+}
+
+/// Computes the check character of an NHI from its first six characters (its body) and format
+///
+/// This is a reference implementation of the final step of the NHI Validation Routine:
+///
+/// 1. Each character of the body is given a code: digits are their value, and letters are
+///    their position in the alphabet excluding `I` and `O` (`A` is `1` through to `Z` is `24`)
+/// 2. Each code is multiplied by its weight, see [checksum_weights], and the products are
+///    summed
+/// 3. For the old format, the sum is taken modulo 11. If the result is `0` the body has no
+///    valid check digit, otherwise the check digit is `11` minus the result, with `10`
+///    becoming `0`
+/// 4. For the new format, the sum is taken modulo 23 and the check character is the letter
+///    whose code is `23` minus the result
+///
+/// # Arguments
+///
+/// * `body`: the first six characters of a potential NHI, checked case-insensitively
+/// * `format`: the format of the NHI
+///
+/// returns: Result<char, ParseNhiError>, with [ParseNhiError::InvalidLength] if the body is
+/// not six characters long, [ParseNhiError::NonAscii], [ParseNhiError::ForbiddenLetter] or
+/// [ParseNhiError::InvalidFormat] if it does not match the character pattern of the format, or
+/// [ParseNhiError::NoValidCheckDigit] if it is an old format body with no valid check digit
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{compute_check_char, NhiFormat, ParseNhiError};
+///
+/// assert_eq!(compute_check_char("ZAC536", NhiFormat::Old), Ok('1'));
+/// assert_eq!(compute_check_char("zbn77v", NhiFormat::New), Ok('L'));
+/// assert_eq!(compute_check_char("ZZZ004", NhiFormat::Old), Err(ParseNhiError::NoValidCheckDigit));
+/// ```
+pub fn compute_check_char(body: &str, format: NhiFormat) -> Result<char, ParseNhiError> {
+    if body.chars().count() != 6 {
+        return Err(ParseNhiError::InvalidLength);
+    }
+    non_ascii(body)?;
+    let mut bytes = [0; 6];
+    bytes.copy_from_slice(body.as_bytes());
+    bytes.make_ascii_uppercase();
+    let body = core::str::from_utf8(&bytes).unwrap();
+    let last_valid = match format {
+        NhiFormat::Old => bytes[5].is_ascii_digit(),
+        NhiFormat::New => LETTERS.contains(&bytes[5]),
+    };
+    if !bytes[..3].iter().all(|b| LETTERS.contains(b))
+        || !bytes[3..5].iter().all(u8::is_ascii_digit)
+        || !last_valid
+    {
+        return Err(forbidden_letter(&bytes).unwrap_or(ParseNhiError::InvalidFormat));
+    }
+    expected_check_char(body).ok_or(ParseNhiError::NoValidCheckDigit)
+}
+
+/// Computes the check character of an NHI from its first six characters (its body), inferring
+/// its format from the body
+///
+/// Bodies ending in a digit are taken to be old format and bodies ending in anything else new
+/// format, so this suits computing the check character as the body is typed. See
+/// [compute_check_char] for how the check character is computed.
+///
+/// # Arguments
+///
+/// * `body`: the first six characters of a potential NHI, checked case-insensitively
+///
+/// returns: Result<char, ParseNhiError>, with the same errors as [compute_check_char]
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{infer_check_char, is_nhi, ParseNhiError};
+///
+/// assert_eq!(infer_check_char("ZAC536"), Ok('1'));
+/// assert_eq!(infer_check_char("zbn77v"), Ok('L'));
+/// assert_eq!(infer_check_char("ZBN77"), Err(ParseNhiError::InvalidLength));
+///
+/// let body = "ZHW58C";
+/// assert!(is_nhi(&format!("{body}{}", infer_check_char(body).unwrap())));
+/// ```
+pub fn infer_check_char(body: &str) -> Result<char, ParseNhiError> {
+    let format = match body.chars().nth(5) {
+        Some(c) if c.is_ascii_digit() => NhiFormat::Old,
+        _ => NhiFormat::New,
+    };
+    compute_check_char(body, format)
+}
+
+/// Checks whether a string has the character pattern of an old format NHI (three letters
+/// excluding `I` and `O`, then four digits) without checking its check digit
+///
+/// # Examples
+///
+/// ```
+/// use nhi::old_format_positions_valid;
+///
+/// assert_eq!(old_format_positions_valid("zzz0044"), true);
+/// assert_eq!(old_format_positions_valid("ZZZ00A4"), false);
+/// ```
+pub fn old_format_positions_valid(s: &str) -> bool {
+    ascii_uppercase_nhi(s).is_some_and(|nhi| matches_format(&nhi, NhiFormat::Old))
+}
+
+/// Checks whether a string has the character pattern of a new format NHI (three letters, two
+/// digits and then two letters, with letters excluding `I` and `O`) without checking its check
+/// character
+///
+/// # Examples
+///
+/// ```
+/// use nhi::new_format_positions_valid;
+///
+/// assert_eq!(new_format_positions_valid("zzz00aa"), true);
+/// assert_eq!(new_format_positions_valid("ZZZ0AAA"), false);
+/// ```
+pub fn new_format_positions_valid(s: &str) -> bool {
+    ascii_uppercase_nhi(s).is_some_and(|nhi| matches_format(&nhi, NhiFormat::New))
+}
+
+/// Returns the indices of the items that are not valid NHI numbers, in ascending order
+///
+/// An empty slice produces an empty vec.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::invalid_indices;
+///
+/// assert_eq!(invalid_indices(&["ZAC5361", "ZZZ0044", "ZBN77VL", ""]), vec![1, 3]);
+/// ```
+pub fn invalid_indices(items: &[&str]) -> Vec<usize> {
+    items.iter()
+        .enumerate()
+        .filter(|(_, item)| !is_nhi(item))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the indices of the items that are valid NHI numbers, in ascending order
+///
+/// This is the inverse of [invalid_indices]. An empty slice produces an empty vec.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::valid_indices;
+///
+/// assert_eq!(valid_indices(&["ZAC5361", "ZZZ0044", "ZBN77VL", ""]), vec![0, 2]);
+/// ```
+pub fn valid_indices(items: &[&str]) -> Vec<usize> {
+    items.iter()
+        .enumerate()
+        .filter(|(_, item)| is_nhi(item))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns the weights applied to the first six characters of an NHI by the NHI Validation
+/// Routine's checksum, in order
+///
+/// Each character's code (its value for digits, or its position in the alphabet excluding `I`
+/// and `O` for letters) is multiplied by its weight and the products are summed.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::checksum_weights;
+///
+/// assert_eq!(checksum_weights(), [7, 6, 5, 4, 3, 2]);
+/// ```
+pub const fn checksum_weights() -> [u32; 6] {
+    [7, 6, 5, 4, 3, 2]
+}
+
+/// Returns the weighted contribution of each of the first six characters of a potential NHI to
+/// the NHI Validation Routine's checksum, in order
+///
+/// Each contribution is the character's code multiplied by its weight, see [checksum_weights].
+/// Their sum, modulo 11 for the old format or 23 for the new format, is the checksum, see
+/// [NHI::checksum].
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI or NHI body, checked case-insensitively. Only its first six
+///   characters are used
+///
+/// returns: `None` if the string is shorter than six characters, or if any of its first six
+/// characters is not a digit or legal letter
+///
+/// # Examples
+///
+/// ```
+/// use nhi::checksum_contributions;
+///
+/// assert_eq!(checksum_contributions("ZBN77VL"), Some([168, 12, 65, 28, 21, 40]));
+/// assert_eq!(checksum_contributions("zbn77v"), Some([168, 12, 65, 28, 21, 40]));
+/// assert_eq!(checksum_contributions("ZBO77V"), None);
+/// ```
+pub fn checksum_contributions(s: &str) -> Option<[u32; 6]> {
+    let bytes = s.as_bytes().get(..6)?;
+    let mut contributions = checksum_weights();
+    for (contribution, b) in contributions.iter_mut().zip(bytes) {
+        *contribution *= char_code(b.to_ascii_uppercase() as char)?;
+    }
+    Some(contributions)
+}
+
+/// Validates a string against the NHI Validation Routine, returning its uppercase bytes
+///
+/// The string is uppercased into a fixed buffer as it is checked, so validation never
+/// allocates. This is a `const fn` so that [is_nhi], and with it [nhi!] and
+/// [NHI::from_str_const], share the exact checks of [NHI::from_str].
+const fn validate_uppercase(s: &str) -> Result<[u8; 7], ParseNhiError> {
+    if char_count(s) != 7 {
+        return Err(ParseNhiError::InvalidLength);
+    }
+    // NHIs are ASCII-only, so non-ASCII input is rejected before the cheaper ASCII uppercase
+    if let Err(error) = non_ascii(s) {
+        return Err(error);
+    }
+    let Some(bytes) = ascii_uppercase_nhi(s) else {
+        return Err(ParseNhiError::InvalidFormat);
+    };
+    if !matches_format(&bytes, NhiFormat::Old) && !matches_format(&bytes, NhiFormat::New) {
+        return Err(match forbidden_letter(&bytes) {
+            Some(error) => error,
+            None => unexpected_character(&bytes),
+        });
+    }
+    // ASCII is always valid UTF-8
+    let Ok(body) = core::str::from_utf8(bytes.split_at(6).0) else {
+        return Err(ParseNhiError::InvalidFormat);
+    };
+    let found = bytes[6] as char;
+    match expected_check_char(body) {
+        None => Err(ParseNhiError::NoValidCheckDigit),
+        Some(expected) if expected as u32 != found as u32 => {
+            Err(ParseNhiError::ChecksumMismatch { expected, found })
+        }
+        Some(_) => Ok(bytes),
+    }
+}
+
+/// Counts the chars of a string, i.e. its bytes that do not continue a multi-byte char
+const fn char_count(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] & 0b1100_0000 != 0b1000_0000 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
+
+/// Emits a `tracing` debug event recording why a string is not a valid NHI, with the string's
+/// length in characters but never the string itself
+#[cfg(feature = "tracing")]
+fn trace_invalid(s: &str, error: &ParseNhiError) {
+    tracing::debug!(reason = error.name(), length = s.chars().count(), "invalid NHI");
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+fn trace_invalid(_: &str, _: &ParseNhiError) {}
+
+/// Uppercases a string of seven ASCII characters into a fixed buffer, or returns `None` if it
+/// is any other string
+const fn ascii_uppercase_nhi(s: &str) -> Option<[u8; 7]> {
+    let bytes = s.as_bytes();
+    if bytes.len() != 7 {
+        return None;
+    }
+    let mut nhi = [0; 7];
+    let mut i = 0;
+    while i < nhi.len() {
+        if !bytes[i].is_ascii() {
+            return None;
+        }
+        nhi[i] = bytes[i].to_ascii_uppercase();
+        i += 1;
+    }
+    Some(nhi)
+}
+
+/// Checks whether seven uppercase bytes have the character pattern of the given format, i.e.
+/// three legal letters, two digits and then two digits (old format) or two legal letters (new
+/// format)
+const fn matches_format(nhi: &[u8; 7], format: NhiFormat) -> bool {
+    let tail_valid = match format {
+        NhiFormat::Old => nhi[5].is_ascii_digit() && nhi[6].is_ascii_digit(),
+        NhiFormat::New => is_letter(nhi[5]) && is_letter(nhi[6]),
+    };
+    is_letter(nhi[0]) && is_letter(nhi[1]) && is_letter(nhi[2])
+        && nhi[3].is_ascii_digit() && nhi[4].is_ascii_digit()
+        && tail_valid
+}
+
+/// Checks whether a byte is a legal uppercase NHI letter
+const fn is_letter(b: u8) -> bool {
+    matches!(b, b'A'..=b'H' | b'J'..=b'N' | b'P'..=b'Z')
+}
+
+/// Returns a [ParseNhiError::NonAscii] error for the first non-ASCII character of a string, if
+/// it has one
+const fn non_ascii(s: &str) -> Result<(), ParseNhiError> {
+    // Every byte before the first non-ASCII byte is a whole char, so byte and char indices agree
+    let bytes = s.as_bytes();
+    let mut index = 0;
+    while index < bytes.len() {
+        if !bytes[index].is_ascii() {
+            return Err(ParseNhiError::NonAscii { index });
+        }
+        index += 1;
+    }
+    Ok(())
+}
+
+/// Returns a [ParseNhiError::ForbiddenLetter] error for the first `I` or `O` in uppercase ASCII
+/// bytes, if they have one
+const fn forbidden_letter(bytes: &[u8]) -> Option<ParseNhiError> {
+    let mut index = 0;
+    while index < bytes.len() {
+        if matches!(bytes[index], b'I' | b'O') {
+            return Some(ParseNhiError::ForbiddenLetter { letter: bytes[index] as char, index });
+        }
+        index += 1;
+    }
+    None
+}
+
+/// Describes the first position of seven uppercase ASCII bytes that breaks the character
+/// pattern of both formats, as a [ParseNhiError::UnexpectedCharacter]
+///
+/// Forbidden letters are not considered, so bytes containing one must already have been
+/// rejected with [ParseNhiError::ForbiddenLetter].
+const fn unexpected_character(bytes: &[u8; 7]) -> ParseNhiError {
+    let mut index = 0;
+    while index < bytes.len() {
+        let b = bytes[index];
+        let (expected, found) = match index {
+            0..=2 => (CharacterClass::Letter, b.is_ascii_uppercase()),
+            3 | 4 => (CharacterClass::Digit, b.is_ascii_digit()),
+            // Either class is allowed, and decides the class of the check character
+            5 => (CharacterClass::Alphanumeric, b.is_ascii_alphanumeric()),
+            _ if bytes[5].is_ascii_digit() => (CharacterClass::Digit, b.is_ascii_digit()),
+            _ => (CharacterClass::Letter, b.is_ascii_uppercase()),
+        };
+        if !found {
+            return ParseNhiError::UnexpectedCharacter { index, expected };
+        }
+        index += 1;
+    }
+    // Unreachable for bytes that break the pattern without a forbidden letter
+    ParseNhiError::InvalidFormat
+}
+
+/// Computes the weighted sum of the first six characters of an NHI, or `None` if any of them is
+/// not a digit or legal uppercase letter
+const fn checksum(nhi: &str) -> Option<u32> {
+    // Bytes are checked rather than chars: no byte of a non-ASCII char is a legal character
+    let bytes = nhi.as_bytes();
+    let weights = checksum_weights();
+    let mut sum = 0;
+    let mut i = 0;
+    while i < weights.len() && i < bytes.len() {
+        match char_code(bytes[i] as char) {
+            Some(code) => sum += code * weights[i],
+            None => return None,
+        }
+        i += 1;
+    }
+    Some(sum)
+}
+
+/// The legal NHI letters, in ascending order of their char codes
+const LETTERS: &[u8; 24] = b"ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// Computes the check character for the first six characters of an uppercase NHI that already
+/// match one of the formats, or `None` for an old format body with a checksum of 0
+const fn expected_check_char(body: &str) -> Option<char> {
+    let Some(checksum) = checksum(body) else {
+        return None;
+    };
+    if matches!(body.as_bytes().last(), Some(b) if b.is_ascii_digit()) {
+        let checksum = checksum % 11;
+        if checksum == 0 {
+            return None;
+        }
+        char::from_digit((11 - checksum) % 10, 10)
+    } else {
+        let check_code = 23 - checksum % 23;
+        Some(LETTERS[check_code as usize - 1] as char)
+    }
+}
+
+/// Returns the value of a digit, or the position of a legal uppercase letter in the NHI
+/// alphabet, or `None` for any other character
+const fn char_code(char: char) -> Option<u32> {
+    match char {
+        '0'..='9' => char.to_digit(10),
+        'A'..='H' => Some(char as u32 - 64),
+        'J'..='N' => Some(char as u32 - 65),
+        'P'..='Z' => Some(char as u32 - 66),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use super::*;
+
+    /// Counts the allocations made on each thread, see [allocations]
+    struct CountingAllocator;
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    /// Returns the result of the given function and the number of allocations it made
+    fn allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        let before = ALLOCATIONS.with(Cell::get);
+        let result = f();
+        (result, ALLOCATIONS.with(Cell::get) - before)
+    }
+
+    pub(crate) const VALID_OLD: [&str; 15] = [
+        "JBX3656", "ZZZ0016", "ZZZ0024", "ZAA0067", "ZAA0075", "ZAA0083", "ZAA0091",
+        "ZAA0105", "ZAA0113", "ZAA0121", "ZAA0130", "ZAA0148", "ZAA0156", "ZAC5361",
+        "ABC1235",
+    ];
+    pub(crate) const VALID_NEW: [&str; 11] = [
+        "ZBN77VL", "ZZZ00AC", "ZDR69YX", "ZSC21TN", "ZZB30NH", "ZYZ81ZV", "ZVB97XQ",
+        "ZRA29VA", "ZYX61YS", "ABC12AY", "XYZ12AN",
+    ];
+    pub(crate) const INVALID_OLD: [&str; 3] = ["ZZZ0044", "ZZZ0017", "DAB8233"];
+    pub(crate) const INVALID_NEW: [&str; 4] = ["ZZZ00AA", "ZZZ00AY", "ZVU27KY", "ZVU27KA"];
+    pub(crate) const RANDOM_STRINGS: [&str; 7] = [
+        "not an NHI", "!@#$%&*", "AAANNNC", "AAANNAC", "ZVU27K", "JBX365", "",
+    ];
+
+    #[test]
+    fn is_nhi_recognises_valid_old_format_nhi_numbers() {
+        for nhi in VALID_OLD {
+            assert!(is_nhi(nhi));
+        }
+    }
+
+    #[test]
+    fn is_nhi_rejects_invalid_old_format_nhi_numbers() {
+        for nhi in INVALID_OLD {
+            assert!(!is_nhi(nhi));
+        }
+        // Needs a check digit of 6
+        for i in 0..10 {
+            if i != 6 {
+                assert!(!is_nhi(&format!("JBX365{i}")));
+            }
+        }
+    }
+
+    #[test]
+    fn no_digit_can_be_added_to_an_old_format_nhi_with_a_checksum_of_0_to_make_it_valid() {
+        for i in 0..10 {
+            assert!(!is_nhi(&format!("ZZZ004{i}")));
+        }
+    }
+
+    #[test]
+    fn is_nhi_recognises_valid_new_format_nhi_numbers() {
+        for nhi in VALID_NEW {
+            assert!(is_nhi(nhi));
+        }
+    }
+
+    #[test]
+    fn is_nhi_rejects_invalid_new_format_nhi_numbers() {
+        for nhi in INVALID_NEW {
+            assert!(!is_nhi(nhi));
+        }
+        // Needs a check character of V
+        for c in "ABCDEFGHJKLMNPQRSTUWXYZ".chars() {
+            assert!(!is_nhi(&format!("ZHW58C{c}")));
+        }
+    }
+
+    #[test]
+    fn is_nhi_rejects_random_strings() {
+        for nhi in RANDOM_STRINGS {
+            assert!(!is_nhi(nhi));
+        }
+    }
+
+    #[test]
+    fn is_nhi_rejects_valid_nhi_numbers_embedded_in_longer_strings() {
+        for nhi in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            for extra in ["X", "0", "VL", " ", "\n", "\0", "ZBN77VL"] {
+                assert!(!is_nhi(&format!("{nhi}{extra}")));
+                assert!(!is_nhi(&format!("{extra}{nhi}")));
+            }
+        }
+        assert!(!is_nhi("ZBN77VLX"));
+    }
+
+    #[test]
+    fn is_nhi_agrees_with_parsing() {
+        let strings = VALID_OLD.iter()
+            .chain(VALID_NEW.iter())
+            .chain(INVALID_OLD.iter())
+            .chain(INVALID_NEW.iter())
+            .chain(RANDOM_STRINGS.iter())
+            .chain(["zbn77vl", "ZBN77VLX", "ZBÑ77VL", "ZBN77VＬ", "IBN77VL", "zzz0O16"].iter());
+        for nhi_str in strings {
+            assert_eq!(is_nhi(nhi_str), NHI::from_str(nhi_str).is_ok(), "{nhi_str}");
+        }
+        for nhi in (0..).map_while(|i| NHI::from_index_within_prefix("ZAC", i)) {
+            assert!(is_nhi(nhi.as_str()));
+            let mut wrong = nhi.into_string();
+            let check = wrong.pop().unwrap();
+            wrong.push(if check == '0' { '1' } else { '0' });
+            assert_eq!(is_nhi(&wrong), NHI::from_str(&wrong).is_ok());
+        }
+    }
+
+    #[test]
+    fn nhi_literals_are_checked_at_compile_time() {
+        assert_eq!(nhi!("ZBN77VL").as_str(), "ZBN77VL");
+        assert_eq!(nhi!("zac5361").as_str(), "ZAC5361");
+        assert_eq!(nhi!("jbx3656"), NHI::from_str("JBX3656").unwrap());
+    }
+
+    #[test]
+    fn is_nhi_is_case_insensitive() {
+        for nhi in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            assert!(is_nhi(&nhi.to_lowercase()));
+        }
+        for nhi in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            assert!(!is_nhi(&nhi.to_lowercase()));
+        }
+    }
+
+    #[test]
+    fn versioned_checks_report_the_default_version() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            assert_eq!(is_nhi_versioned(nhi_str), (true, HisoVersion::V2023));
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            assert_eq!(is_nhi_versioned(nhi_str), (false, HisoVersion::V2023));
+        }
+    }
+
+    #[test]
+    fn format_positions_are_checked_without_the_checksum() {
+        for nhi in VALID_OLD.iter().chain(INVALID_OLD.iter()) {
+            assert!(old_format_positions_valid(nhi));
+            assert!(old_format_positions_valid(&nhi.to_lowercase()));
+            assert!(!new_format_positions_valid(nhi));
+        }
+        for nhi in VALID_NEW.iter().chain(INVALID_NEW.iter()) {
+            assert!(new_format_positions_valid(nhi));
+            assert!(new_format_positions_valid(&nhi.to_lowercase()));
+            assert!(!old_format_positions_valid(nhi));
+        }
+        for nhi in RANDOM_STRINGS.iter().chain(["ZB177VL", "ZBN7AVL", "ZBN77V1", "IBN77VL"].iter()) {
+            assert!(!old_format_positions_valid(nhi));
+            assert!(!new_format_positions_valid(nhi));
+        }
+    }
+
+    #[test]
+    fn invalid_and_valid_indices_partition_a_slice() {
+        let items: Vec<&str> = VALID_OLD.iter()
+            .zip(INVALID_NEW.iter())
+            .flat_map(|(valid, invalid)| [*valid, *invalid])
+            .collect();
+        let valid: Vec<usize> = (0..items.len()).step_by(2).collect();
+        let invalid: Vec<usize> = (1..items.len()).step_by(2).collect();
+        assert_eq!(valid_indices(&items), valid);
+        assert_eq!(invalid_indices(&items), invalid);
+        assert!(valid_indices(&[]).is_empty());
+        assert!(invalid_indices(&[]).is_empty());
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_parsed_from_strings_to_results() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            assert!(NHI::from_str(nhi_str).is_ok());
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            assert!(NHI::from_str(nhi_str).is_err());
+        }
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_created_in_const_contexts() {
+        const OLD: NHI = NHI::from_str_const("ZAC5361");
+        const NEW: NHI = NHI::from_str_const("ZBN77VL");
+        static CLINIC: NHI = NHI::from_str_const("JBX3656");
+        const _: NHI = NHI::from_str_const("ZHW58CV");
+        assert_eq!(OLD, NHI::from_str("zac5361").unwrap());
+        assert_eq!(NEW, NHI::from_str("zbn77vl").unwrap());
+        assert!(CLINIC.is_not_test());
+        assert_eq!(OLD.into_string(), "ZAC5361");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid NHI")]
+    fn invalid_nhi_numbers_cannot_be_created_in_const_contexts() {
+        NHI::from_str_const("ZBN77VK");
+    }
+
+    #[test]
+    #[should_panic(expected = "NHI must be uppercase")]
+    fn lowercase_nhi_numbers_cannot_be_created_in_const_contexts() {
+        NHI::from_str_const("zbn77vl");
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_parsed_from_bytes() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            assert_eq!(NHI::from_bytes(nhi_str.as_bytes()), Ok(nhi.clone()));
+            assert_eq!(NHI::from_bytes(nhi_str.to_lowercase().as_bytes()), Ok(nhi));
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            if nhi_str.is_ascii() {
+                assert_eq!(NHI::from_bytes(nhi_str.as_bytes()), NHI::from_str(nhi_str));
+            }
+        }
+        assert_eq!(NHI::from_bytes(b"ZBN77V\xFF"), Err(ParseNhiError::NonAscii { index: 6 }));
+        assert_eq!(NHI::from_bytes(b"\xFFBN77VL"), Err(ParseNhiError::NonAscii { index: 0 }));
+        assert_eq!(NHI::from_bytes(b"ZBN77\xC3\x91"), Err(ParseNhiError::NonAscii { index: 5 }));
+        assert_eq!(NHI::from_bytes("ZBÑ77VL".as_bytes()), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::from_bytes(b""), Err(ParseNhiError::InvalidLength));
+        assert_eq!(NHI::from_bytes(b"ZBN77VL "), Err(ParseNhiError::InvalidLength));
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_converted_from_strings() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            assert_eq!(NHI::try_from(*nhi_str), Ok(nhi.clone()));
+            assert_eq!(NHI::try_from(nhi_str.to_lowercase().as_str()), Ok(nhi.clone()));
+            assert_eq!(NHI::try_from(nhi_str.to_string()), Ok(nhi.clone()));
+            assert_eq!(NHI::try_from(nhi_str.to_lowercase()), Ok(nhi));
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            let error = NHI::from_str(nhi_str).unwrap_err();
+            assert_eq!(NHI::try_from(*nhi_str), Err(error));
+            assert_eq!(NHI::try_from(nhi_str.to_string()), Err(error));
+        }
+    }
+
+    #[test]
+    fn differently_cased_nhi_numbers_are_equal_and_hash_identically() {
+        use std::collections::HashMap;
+        use std::hash::BuildHasher;
+
+        let hasher = std::collections::hash_map::RandomState::new();
+        let mut counts: HashMap<NHI, usize> = HashMap::new();
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let upper = NHI::from_str(nhi_str).unwrap();
+            let lower = NHI::from_str(&nhi_str.to_lowercase()).unwrap();
+            let owned = NHI::try_from(nhi_str.to_lowercase()).unwrap();
+            assert_eq!(upper, lower);
+            assert_eq!(upper, owned);
+            assert_eq!(hasher.hash_one(&upper), hasher.hash_one(&lower));
+            assert_eq!(hasher.hash_one(&upper), hasher.hash_one(&owned));
+            for nhi in [upper, lower, owned] {
+                *counts.entry(nhi).or_default() += 1;
+            }
+        }
+        assert_eq!(counts.len(), VALID_OLD.len() + VALID_NEW.len());
+        assert!(counts.values().all(|&count| count == 3));
+    }
+
+    #[test]
+    fn owned_strings_are_moved_into_nhi_numbers() {
+        let s = String::from("zbn77vl");
+        let ptr = s.as_ptr();
+        let nhi = NHI::try_from(s).unwrap();
+        assert_eq!(nhi.as_str(), "ZBN77VL");
+        assert_eq!(nhi.as_str().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_nhis_can_be_excluded() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            for s in [nhi_str.to_string(), nhi_str.to_lowercase()] {
+                let is_test = nhi_str.starts_with('Z');
+                assert_eq!(is_nhi_excluding_test(&s), !is_test);
+                match NHI::parse_non_test(&s) {
+                    Ok(nhi) => assert_eq!(nhi.as_str(), *nhi_str),
+                    Err(error) => {
+                        assert!(is_test);
+                        assert_eq!(error, ParseNhiError::ReservedForTesting);
+                        assert_eq!(error.rule(), Rule::TestPrefix);
+                    }
+                }
+            }
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            assert!(!is_nhi_excluding_test(nhi_str));
+            assert_eq!(NHI::parse_non_test(nhi_str), NHI::from_str(nhi_str));
+        }
+        assert!(!is_nhi_excluding_test(""));
+    }
+
+    #[test]
+    fn parse_errors_map_to_the_rule_broken() {
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()) {
+            let error = NHI::from_str(nhi_str).unwrap_err();
+            assert_eq!(error.rule(), Rule::CheckDigit);
+        }
+        for nhi_str in ["", "ZVU27K", "JBX365", "ZBN77VLX", "not an NHI"] {
+            let error = NHI::from_str(nhi_str).unwrap_err();
+            assert_eq!(error, ParseNhiError::InvalidLength);
+            assert_eq!(error.rule(), Rule::Length);
+        }
+        let cases = [
+            ("!@#$%&*", 0, CharacterClass::Letter),
+            ("AAANNNC", 3, CharacterClass::Digit),
+            ("AAANNAC", 3, CharacterClass::Digit),
+            ("ZBN77V1", 6, CharacterClass::Letter),
+        ];
+        for (nhi_str, index, expected) in cases {
+            let error = NHI::from_str(nhi_str).unwrap_err();
+            assert_eq!(error, ParseNhiError::UnexpectedCharacter { index, expected });
+            assert_eq!(error.rule(), Rule::CharacterSet);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn nhi_numbers_round_trip_through_json() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            let json = serde_json::to_string(&nhi).unwrap();
+            assert_eq!(json, format!("\"{nhi_str}\""));
+            assert_eq!(serde_json::from_str::<NHI>(&json).unwrap(), nhi);
+            let lowercase = json.to_lowercase();
+            assert_eq!(serde_json::from_str::<NHI>(&lowercase).unwrap(), nhi);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn invalid_nhi_numbers_fail_to_deserialize() {
+        let invalid = INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter());
+        for nhi_str in invalid {
+            let json = serde_json::to_string(nhi_str).unwrap();
+            let error = serde_json::from_str::<NHI>(&json).unwrap_err();
+            assert_eq!(error.to_string(), NHI::from_str(nhi_str).unwrap_err().to_string());
+        }
+        assert!(serde_json::from_str::<NHI>("7").is_err());
+        assert!(serde_json::from_str::<NHI>("null").is_err());
+    }
+
+    #[test]
+    fn inferred_check_chars_complete_valid_nhi_numbers() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let body = nhi_str[..6].to_lowercase();
+            let nhi = format!("{body}{}", infer_check_char(&body).unwrap());
+            assert!(is_nhi(&nhi));
+            assert_eq!(nhi.to_uppercase(), *nhi_str);
+        }
+        for nhi in (0..).map_while(|i| NHI::from_index_within_prefix("JBX", i)) {
+            let body = &nhi.as_str()[..6];
+            assert!(is_nhi(&format!("{body}{}", infer_check_char(body).unwrap())));
+        }
+    }
+
+    #[test]
+    fn inferred_check_chars_reject_invalid_bodies() {
+        assert_eq!(infer_check_char(""), Err(ParseNhiError::InvalidLength));
+        assert_eq!(infer_check_char("ZBN77VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(infer_check_char("ZZZ004"), Err(ParseNhiError::NoValidCheckDigit));
+        assert_eq!(infer_check_char("ZB177V"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(infer_check_char("ZBN77!"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(
+            infer_check_char("ZBN77O"),
+            Err(ParseNhiError::ForbiddenLetter { letter: 'O', index: 5 }),
+        );
+    }
+
+    #[test]
+    fn nhi_numbers_report_their_format() {
+        for nhi_str in VALID_OLD {
+            assert_eq!(NHI::from_str(nhi_str).unwrap().format(), NhiFormat::Old);
+        }
+        for nhi_str in VALID_NEW {
+            assert_eq!(NHI::from_str(nhi_str).unwrap().format(), NhiFormat::New);
+        }
+    }
+
+    #[test]
+    fn the_first_character_of_the_wrong_class_is_reported() {
+        use CharacterClass::{Alphanumeric, Digit, Letter};
+        let unexpected = |index, expected| Err(ParseNhiError::UnexpectedCharacter { index, expected });
+        for nhi_str in ["AAANNNC", "AAANNAC", "ZBNVVVL", "zbnvvvl", "ZACZACZ"] {
+            assert_eq!(NHI::from_str(nhi_str), unexpected(3, Digit));
+        }
+        assert_eq!(NHI::from_str("ZB177VL"), unexpected(2, Letter));
+        assert_eq!(NHI::from_str("1BN77VL"), unexpected(0, Letter));
+        assert_eq!(NHI::from_str("ZAC5V61"), unexpected(4, Digit));
+        assert_eq!(NHI::from_str("ZAC536A"), unexpected(6, Digit));
+        assert_eq!(NHI::from_str("ZBN77V1"), unexpected(6, Letter));
+        assert_eq!(NHI::from_str("ZBN7VV1"), unexpected(4, Digit));
+        // Punctuation is of neither class, so is unexpected wherever it is
+        assert_eq!(NHI::from_str("!@#$%&*"), unexpected(0, Letter));
+        assert_eq!(NHI::from_str("ZBN77!L"), unexpected(5, Alphanumeric));
+        assert_eq!(NHI::from_str("Z-N7VVL"), unexpected(1, Letter));
+        assert_eq!(NHI::from_str("ZBN7-VL"), unexpected(4, Digit));
+    }
+
+    #[test]
+    fn parse_errors_are_as_specific_as_possible() {
+        assert_eq!(NHI::from_str("ZBN77V"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(
+            NHI::from_str("ZBN77V!"),
+            Err(ParseNhiError::UnexpectedCharacter { index: 6, expected: CharacterClass::Letter }),
+        );
+        assert_eq!(
+            NHI::from_str("IBN77VL"),
+            Err(ParseNhiError::ForbiddenLetter { letter: 'I', index: 0 }),
+        );
+        assert_eq!(
+            NHI::from_str("zbo7o7l"),
+            Err(ParseNhiError::ForbiddenLetter { letter: 'O', index: 2 }),
+        );
+        assert_eq!(
+            NHI::from_str("zbn77vk"),
+            Err(ParseNhiError::ChecksumMismatch { expected: 'L', found: 'K' }),
+        );
+        assert_eq!(
+            NHI::from_str("JBX3657"),
+            Err(ParseNhiError::ChecksumMismatch { expected: '6', found: '7' }),
+        );
+        assert_eq!(NHI::from_str("ZZZ0044"), Err(ParseNhiError::NoValidCheckDigit));
+        assert_eq!(NHI::from_str("DAB8233"), Err(ParseNhiError::NoValidCheckDigit));
+    }
+
+    #[test]
+    fn ascii_uppercasing_matches_unicode_uppercasing_for_nhi_numbers() {
+        let all = VALID_OLD.iter()
+            .chain(VALID_NEW.iter())
+            .chain(INVALID_OLD.iter())
+            .chain(INVALID_NEW.iter());
+        for nhi_str in all {
+            for s in [nhi_str.to_string(), nhi_str.to_lowercase()] {
+                assert_eq!(s.to_ascii_uppercase(), s.to_uppercase());
+            }
+        }
+    }
+
+    #[test]
+    fn non_ascii_characters_are_never_uppercased_into_nhi_numbers() {
+        // U+017F (long s) uppercases to the ASCII `S`
+        assert_eq!("zyx61yſ".to_uppercase(), "ZYX61YS");
+        assert!(is_nhi("ZYX61YS"));
+        assert_eq!(NHI::from_str("zyx61yſ"), Err(ParseNhiError::NonAscii { index: 6 }));
+        assert!(!new_format_positions_valid("zyx61yſ"));
+    }
+
+    #[test]
+    fn non_ascii_characters_are_rejected_with_their_index() {
+        let cases = [
+            ("ZBÑ77VL", 2),
+            ("ZBN77VÉ", 6),
+            ("ＺBN77VL", 0),
+            ("ZBN７7VL", 3),
+            ("😀😀😀😀😀😀😀", 0),
+            ("ZBN77V\u{301}", 6),
+        ];
+        for (input, index) in cases {
+            let error = NHI::from_str(input).unwrap_err();
+            assert_eq!(error, ParseNhiError::NonAscii { index });
+            assert_eq!(error.rule(), Rule::CharacterSet);
+            assert!(!is_nhi(input));
+        }
+        assert_eq!(NHI::from_str("😀😀😀"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(compute_check_char("ZBN7ÑV", NhiFormat::New), Err(ParseNhiError::NonAscii { index: 4 }));
+        assert_eq!(
+            NHI::from_str("ZBÑ77VL").unwrap_err().to_string(),
+            "invalid NHI: contains a non-ASCII character at index 2",
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn parse_errors_are_boxable_errors_with_readable_messages() {
+        fn parse(s: &str) -> Result<NHI, Box<dyn std::error::Error>> {
+            Ok(s.parse::<NHI>()?)
+        }
+        assert!(parse("ZBN77VL").is_ok());
+        let cases = [
+            ("ZBN77", "invalid NHI: must be exactly seven characters long"),
+            ("ZBN77V!", "invalid NHI: expected a letter at index 6"),
+            ("ZBN77!L", "invalid NHI: expected a letter or digit at index 5"),
+            ("ZBN77V1", "invalid NHI: expected a letter at index 6"),
+            ("ZBO77VL", "invalid NHI: contains the forbidden letter `O` at index 2"),
+            ("ZBN77VK", "invalid NHI: expected check character `L` but found `K`"),
+            ("ZZZ0044", "invalid NHI: no check digit is valid for the first six characters"),
+        ];
+        for (input, message) in cases {
+            assert_eq!(parse(input).unwrap_err().to_string(), message);
+        }
+        let error = NHI::parse_non_test("ZBN77VL").unwrap_err();
+        assert_eq!(error.to_string(), "invalid NHI: begins with `Z` so is reserved for testing");
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_converted_to_strings() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
             let nhi = NHI::from_str(&nhi_str.to_lowercase()).unwrap();
             assert_eq!(nhi.as_str(), nhi_str.to_uppercase());
             assert_eq!(nhi.into_string(), nhi_str.to_uppercase());
         }
     }
 
+    #[test]
+    fn nhi_numbers_can_be_looked_up_by_str() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let nhis: Vec<NHI> = VALID_OLD.iter().chain(VALID_NEW.iter())
+            .map(|s| s.to_lowercase().parse().unwrap())
+            .collect();
+        let hash_set: HashSet<NHI> = nhis.iter().cloned().collect();
+        let btree_set: BTreeSet<NHI> = nhis.iter().cloned().collect();
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            assert!(hash_set.contains(*nhi_str));
+            assert!(btree_set.contains(*nhi_str));
+        }
+        assert!(!hash_set.contains("JBX3657"));
+        assert!(!btree_set.contains("JBX3657"));
+    }
+
+    #[test]
+    fn constant_time_comparisons_agree_with_eq() {
+        let nhis: Vec<NHI> = VALID_OLD.iter().chain(VALID_NEW.iter())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        for a in &nhis {
+            for b in &nhis {
+                assert_eq!(a.ct_eq(b), a == b);
+            }
+            assert!(a.ct_eq(&a.to_string().to_lowercase().parse().unwrap()));
+        }
+    }
+
+    #[test]
+    fn nhi_numbers_equal_strings_case_insensitively() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            let lowercase = nhi_str.to_lowercase();
+            let mixed: String = nhi_str.chars().enumerate()
+                .map(|(i, c)| if i % 2 == 0 { c.to_ascii_lowercase() } else { c })
+                .collect();
+            for s in [nhi_str.to_string(), lowercase, mixed] {
+                assert!(nhi == *s.as_str());
+                assert!(*s.as_str() == nhi);
+                assert_eq!(nhi, s.as_str());
+                assert_eq!(s.as_str(), nhi);
+                assert_eq!(nhi, s);
+                assert_eq!(s, nhi);
+            }
+        }
+        let nhi = NHI::from_str("ZBN77VL").unwrap();
+        for s in ["ZBN77VK", "ZBN77V", "zbn77vl ", "", "ZAC5361"] {
+            let owned = String::from(s);
+            assert_ne!(nhi, s);
+            assert_ne!(s, nhi);
+            assert_ne!(nhi, owned);
+            assert_ne!(owned, nhi);
+        }
+        assert_ne!(nhi.as_str(), "zbn77vl");
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_grouped() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            let grouped = nhi.to_grouped();
+            assert_eq!(grouped, format!("{} {} {}", &nhi_str[..3], &nhi_str[3..5], &nhi_str[5..]));
+            assert_eq!(format!("{nhi:#}"), grouped);
+            assert_eq!(format!("{nhi}"), *nhi_str);
+            assert_eq!(NHI::parse_normalized(&grouped), Ok(nhi));
+        }
+        assert_eq!(NHI::from_str("zac5361").unwrap().to_grouped(), "ZAC 53 61");
+        assert_eq!(NHI::from_str("zhw58cv").unwrap().to_grouped(), "ZHW 58 CV");
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_converted_into_strings() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            assert_eq!(String::from(&nhi), nhi.as_str());
+            let bytes: &[u8] = nhi.as_ref();
+            assert_eq!(bytes, nhi_str.as_bytes());
+            let s: String = nhi.into();
+            assert_eq!(s, *nhi_str);
+        }
+        let nhi = NHI::try_from("zbn77vl".to_string()).unwrap();
+        let ptr = nhi.as_str().as_ptr();
+        let s = String::from(nhi);
+        assert_eq!(s, "ZBN77VL");
+        assert_eq!(s.as_ptr(), ptr);
+    }
+
+    #[test]
+    fn nhi_numbers_can_be_used_as_strs() {
+        fn len(s: impl AsRef<str>) -> usize {
+            s.as_ref().len()
+        }
+        let nhi = NHI::from_str("zbn77vl").unwrap();
+        assert_eq!(len(&nhi), 7);
+        assert_eq!(nhi.as_ref() as &str, "ZBN77VL");
+        assert!(nhi.starts_with("ZBN"));
+        assert_eq!(&nhi[3..5], "77");
+    }
+
+    #[test]
+    fn index_keys_are_canonical_uppercase() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let lower = NHI::from_str(&nhi_str.to_lowercase()).unwrap();
+            let upper = NHI::from_str(nhi_str).unwrap();
+            assert_eq!(lower.index_key(), *nhi_str);
+            assert_eq!(lower.index_key(), upper.index_key());
+        }
+    }
+
     #[test]
     fn nhi_numbers_identify_values_reserved_for_testing() {
         let reserved = vec!["ZAA0105", "ZAA0113", "ZBN77VL", "ZZZ00AC"];
@@ -322,6 +2420,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn redacted_nhi_numbers_only_reveal_the_prefix() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            let redacted = nhi.redacted();
+            assert_eq!(&redacted[..3], &nhi_str[..3]);
+            assert!(redacted[3..].chars().all(|c| c == '•'));
+            assert_eq!(redacted.chars().count(), 7);
+            assert_eq!(format!("{nhi:?}"), format!("NHI({redacted:?})"));
+            assert!(!format!("{:?}", Some(&nhi)).contains(&nhi_str[3..]));
+        }
+    }
+
     #[test]
     fn nhi_numbers_can_be_formatted() {
         for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
@@ -330,16 +2441,358 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tagged_nhi_numbers_flag_values_reserved_for_testing() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let expected = if nhi.is_test() { format!("{nhi_str} (TEST)") } else { nhi_str.to_string() };
+            assert_eq!(format!("{}", nhi.test_tagged()), expected);
+            assert_eq!(format!("{nhi}"), *nhi_str);
+        }
+    }
+
+    #[test]
+    fn extra_test_prefixes_extend_the_standard_reservation() {
+        let is_test_with = |s: &str, extra: &[&str]| s.parse::<NHI>().unwrap().is_test_with(extra);
+        assert!(is_test_with("ZAA0105", &[]));
+        assert!(is_test_with("ZAA0105", &["JBX"]));
+        assert!(!is_test_with("JBX3656", &[]));
+        assert!(!is_test_with("JBX3656", &[""]));
+        assert!(is_test_with("JBX3656", &["ABC", "jbx"]));
+        assert!(is_test_with("JBX3656", &["J"]));
+        assert!(is_test_with("JBX3656", &["JBX3656"]));
+        assert!(!is_test_with("JBX3656", &["JBX36566"]));
+        assert!(!is_test_with("ABC1235", &["JBX", "XYZ"]));
+    }
+
+    #[test]
+    fn test_environments_are_resolved_by_longest_matching_prefix() {
+        let env = |s: &str, map: &TestRangeMap| {
+            s.parse::<NHI>().unwrap().test_environment_with(map).map(str::to_string)
+        };
+        let mut map = TestRangeMap::new();
+        assert_eq!(env("ZAA0105", &map).as_deref(), Some("test"));
+        assert_eq!(env("JBX3656", &map), None);
+
+        map.insert("za", "staging");
+        map.insert("ZAA", "training");
+        map.insert("JBX", "ignored");
+        assert_eq!(env("ZAA0105", &map).as_deref(), Some("training"));
+        assert_eq!(env("ZAC5361", &map).as_deref(), Some("staging"));
+        assert_eq!(env("ZBN77VL", &map).as_deref(), Some("test"));
+        assert_eq!(env("JBX3656", &map), None);
+
+        map.insert("ZAA", "uat");
+        assert_eq!(env("ZAA0105", &map).as_deref(), Some("uat"));
+
+        // Only ASCII letters are uppercased, so `ſ` never becomes `S`
+        map.insert("zbſ", "ignored");
+        let zbs = NHI::from_index_within_prefix("ZBS", 0).unwrap();
+        assert_eq!(env(zbs.as_str(), &map).as_deref(), Some("test"));
+    }
+
+    #[test]
+    fn prefix_ordinals_follow_the_legal_alphabet() {
+        let ordinal = |s: &str| s.parse::<NHI>().unwrap().prefix_ordinal();
+        assert_eq!(ordinal("ABC1235"), 26);
+        assert_eq!(ordinal("JBX3656"), 8 * 576 + 24 + 21);
+        assert_eq!(ordinal("ZZZ0016"), 24 * 24 * 24 - 1);
+
+        let mut nhis: Vec<NHI> = VALID_OLD.iter().chain(VALID_NEW.iter())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        nhis.sort();
+        for pair in nhis.windows(2) {
+            assert!(pair[0].prefix_ordinal() <= pair[1].prefix_ordinal());
+        }
+    }
+
+    #[test]
+    fn check_values_can_be_displayed_for_both_formats() {
+        for nhi_str in VALID_OLD {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            assert_eq!(nhi.check_display(), &nhi_str[6..]);
+            assert_eq!(nhi.check_as_letter(), None);
+        }
+        for nhi_str in VALID_NEW {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            assert_eq!(nhi.check_display(), &nhi_str[6..]);
+            assert_eq!(nhi.check_as_letter(), nhi_str.chars().last());
+        }
+    }
+
+    #[test]
+    fn checksum_weights_are_the_weights_applied_by_the_checksum() {
+        for (i, weight) in checksum_weights().into_iter().enumerate() {
+            let mut body = [b'0'; 6];
+            body[i] = b'1';
+            assert_eq!(checksum(std::str::from_utf8(&body).unwrap()), Some(weight));
+        }
+    }
+
+    #[test]
+    fn format_codes_are_stable_labels_for_each_format() {
+        for nhi_str in VALID_OLD {
+            assert_eq!(NHI::from_str(nhi_str).unwrap().format_code(), "OLD");
+        }
+        for nhi_str in VALID_NEW {
+            assert_eq!(NHI::from_str(nhi_str).unwrap().format_code(), "NEW");
+        }
+    }
+
+    #[test]
+    fn only_valid_nhi_numbers_allocate() {
+        let invalid = INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter())
+            .chain(["ZBO77VL", "zbÑ77vl", "ZBN77VLX"].iter());
+        for nhi_str in invalid {
+            let (result, count) = allocations(|| NHI::from_str(nhi_str));
+            assert!(result.is_err());
+            assert_eq!(count, 0, "{nhi_str}");
+            assert_eq!(allocations(|| is_nhi(nhi_str)), (false, 0));
+            assert_eq!(allocations(|| infer_check_char(nhi_str.get(..6).unwrap_or(""))).1, 0);
+        }
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let lowercase = nhi_str.to_lowercase();
+            assert_eq!(allocations(|| is_nhi(&lowercase)), (true, 0));
+            assert_eq!(allocations(|| infer_check_char(&lowercase[..6]).is_ok()), (true, 0));
+            let (nhi, count) = allocations(|| NHI::from_str(&lowercase).unwrap());
+            assert_eq!(count, 1);
+            assert_eq!(nhi.as_str(), *nhi_str);
+            assert_eq!(allocations(|| NHI::try_from(lowercase).is_ok()), (true, 0));
+        }
+    }
+
+    #[test]
+    fn info_aggregates_the_value_format_and_test_status() {
+        let old: NHI = "zac5361".parse().unwrap();
+        assert_eq!(old.info(), NhiInfo { value: "ZAC5361", format: NhiFormat::Old, is_test: true });
+        let new: NHI = "XYZ12AN".parse().unwrap();
+        assert_eq!(new.info(), NhiInfo { value: "XYZ12AN", format: NhiFormat::New, is_test: false });
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let info = nhi.info();
+            assert_eq!((info.value, info.format, info.is_test), (nhi.as_str(), nhi.format(), nhi.is_test()));
+        }
+    }
+
+    #[test]
+    fn validated_slices_are_the_untouched_input() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let mixed: String = nhi_str.chars().enumerate()
+                .map(|(i, c)| if i % 2 == 0 { c.to_ascii_lowercase() } else { c })
+                .collect();
+            for s in [*nhi_str, &nhi_str.to_lowercase(), &mixed] {
+                let (result, count) = allocations(|| validate(s));
+                assert_eq!(count, 0);
+                let slice = result.unwrap();
+                assert_eq!(slice, s);
+                assert_eq!(slice.as_ptr(), s.as_ptr());
+            }
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(RANDOM_STRINGS.iter()) {
+            assert_eq!(validate(nhi_str).unwrap_err(), NHI::from_str(nhi_str).unwrap_err());
+        }
+    }
+
+    #[test]
+    fn reservations_classify_the_test_prefix() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.to_lowercase().parse().unwrap();
+            let expected = if nhi_str.starts_with('Z') { Reservation::Test } else { Reservation::None };
+            assert_eq!(nhi.reservation(), expected);
+            assert_eq!(nhi.is_test(), expected == Reservation::Test);
+            assert_eq!(nhi.is_not_test(), expected == Reservation::None);
+        }
+    }
+
+    #[test]
+    fn trusted_nhi_numbers_round_trip() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            assert_eq!(NHI::from_trusted(nhi.as_str()), nhi);
+            assert_eq!(NHI::from_trusted(nhi.as_str()).as_str(), *nhi_str);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "not a valid NHI")]
+    fn untrusted_garbage_panics_in_debug_builds() {
+        NHI::from_trusted("not an NHI");
+    }
+
+    #[test]
+    fn formats_are_matched_without_the_checksum() {
+        for (nhi_str, format) in VALID_OLD.iter().chain(INVALID_OLD.iter()).map(|s| (s, NhiFormat::Old))
+            .chain(VALID_NEW.iter().chain(INVALID_NEW.iter()).map(|s| (s, NhiFormat::New)))
+        {
+            assert_eq!(NHI::matches_format(nhi_str), Some(format));
+            assert_eq!(NHI::matches_format(&nhi_str.to_lowercase()), Some(format));
+        }
+        assert_eq!(NHI::matches_format("ZZZ0044"), Some(NhiFormat::Old));
+        assert_eq!(NHI::from_str("ZZZ0044"), Err(ParseNhiError::NoValidCheckDigit));
+        let partial = ["", "Z", "ZBN", "ZBN7", "ZBN77", "ZBN77V", "ZBN77VLX", "ZBO77VL", "ZBÑ77VL"];
+        for s in partial.iter().chain(RANDOM_STRINGS.iter()) {
+            assert_eq!(NHI::matches_format(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn checksums_are_the_sum_of_contributions_modulo_the_format() {
+        let cases = [
+            ("ZAC5361", [168, 6, 15, 20, 9, 12], 10),
+            ("JBX3656", [63, 12, 110, 12, 18, 10], 5),
+            ("ZBN77VL", [168, 12, 65, 28, 21, 40], 12),
+            ("ZHW58CV", [168, 48, 105, 20, 24, 6], 3),
+        ];
+        for (nhi_str, contributions, checksum) in cases {
+            assert_eq!(checksum_contributions(nhi_str), Some(contributions));
+            assert_eq!(NHI::from_str(nhi_str).unwrap().checksum(), checksum);
+        }
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi = NHI::from_str(nhi_str).unwrap();
+            let sum: u32 = checksum_contributions(nhi_str).unwrap().iter().sum();
+            let modulus = if nhi.format() == NhiFormat::Old { 11 } else { 23 };
+            assert_eq!(nhi.checksum(), sum % modulus);
+            assert_eq!(checksum_contributions(&nhi_str[..6].to_lowercase()), checksum_contributions(nhi_str));
+            if nhi.format() == NhiFormat::Old {
+                assert_ne!(nhi.checksum(), 0);
+            }
+        }
+        for s in ["", "ZBN77", "ZBI77V", "ZB-77V", "ZBÑ77V"] {
+            assert_eq!(checksum_contributions(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn check_chars_are_computed_from_bodies() {
+        for nhi_str in VALID_OLD {
+            let check = compute_check_char(&nhi_str[..6].to_lowercase(), NhiFormat::Old);
+            assert_eq!(check, Ok(nhi_str.chars().last().unwrap()));
+        }
+        for nhi_str in VALID_NEW {
+            let check = compute_check_char(&nhi_str[..6].to_lowercase(), NhiFormat::New);
+            assert_eq!(check, Ok(nhi_str.chars().last().unwrap()));
+        }
+        // Worked examples for the test values in the standard:
+        // Z=24: 24*7 + 24*6 + 24*5 + 0*4 + 0*3 + 4*2 = 440, 440 % 11 = 0, so there is no check digit
+        assert_eq!(compute_check_char("ZZZ004", NhiFormat::Old), Err(ParseNhiError::NoValidCheckDigit));
+        // A=1: 24*7 + 24*6 + 24*5 + 0*4 + 0*3 + 1*2 = 434, 434 % 23 = 20, 23 - 20 = 3 = C
+        assert_eq!(compute_check_char("ZZZ00A", NhiFormat::New), Ok('C'));
+    }
+
+    #[test]
+    fn check_chars_are_only_computed_for_bodies_matching_the_format() {
+        assert_eq!(compute_check_char("ZAC53", NhiFormat::Old), Err(ParseNhiError::InvalidLength));
+        assert_eq!(compute_check_char("ZAC5361", NhiFormat::Old), Err(ParseNhiError::InvalidLength));
+        assert_eq!(compute_check_char("ZAC536", NhiFormat::New), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(compute_check_char("ZBN77V", NhiFormat::Old), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(
+            compute_check_char("ZBN77i", NhiFormat::New),
+            Err(ParseNhiError::ForbiddenLetter { letter: 'I', index: 5 }),
+        );
+        assert_eq!(compute_check_char("ZB177V", NhiFormat::New), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(compute_check_char("ZBN7ÑV", NhiFormat::New), Err(ParseNhiError::NonAscii { index: 4 }));
+    }
+
+    #[test]
+    fn old_numeric_bodies_keep_leading_zeros() {
+        for nhi_str in VALID_OLD {
+            let body = NHI::from_str(nhi_str).unwrap().old_numeric_body().map(str::to_string);
+            assert_eq!(body.as_deref(), Some(&nhi_str[3..]));
+        }
+        for nhi_str in VALID_NEW {
+            assert_eq!(NHI::from_str(nhi_str).unwrap().old_numeric_body(), None);
+        }
+        assert_eq!(NHI::from_str("ZZZ0016").unwrap().old_numeric_body(), Some("0016"));
+    }
+
     #[test]
     fn char_codes() {
+        for (i, c) in ('0'..='9').enumerate() {
+            assert_eq!(char_code(c), Some(i as u32));
+        }
         for (i, c) in ('A'..'I').enumerate() {
-            assert_eq!(char_code(c), i as u32 + 1);
+            assert_eq!(char_code(c), Some(i as u32 + 1));
         }
         for (i, c) in ('J'..'O').enumerate() {
-            assert_eq!(char_code(c), i as u32 + 9);
+            assert_eq!(char_code(c), Some(i as u32 + 9));
         }
         for (i, c) in ('P'..='Z').enumerate() {
-            assert_eq!(char_code(c), i as u32 + 14);
+            assert_eq!(char_code(c), Some(i as u32 + 14));
+        }
+    }
+
+    #[test]
+    fn char_codes_of_characters_outside_the_legal_set_are_none() {
+        let legal = |c: char| c.is_ascii_digit() || (c.is_ascii_uppercase() && c != 'I' && c != 'O');
+        for c in (0..=0x3000).chain([0xD7FF, 0xE000, 0xFF3A, 0x1F600, 0x10FFFF]).filter_map(char::from_u32) {
+            if !legal(c) {
+                assert_eq!(char_code(c), None);
+            }
         }
+        assert_eq!(checksum("ZBN77V"), Some(334));
+        assert_eq!(checksum("zbn77v"), None);
+        assert_eq!(checksum("ZBÑ77V"), None);
+        assert_eq!(checksum("!@#$%&"), None);
+        assert_eq!(expected_check_char("ZB 77V"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn failed_parses_emit_tracing_events_without_the_string() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::subscriber::Interest;
+        use tracing::{Event, Metadata};
+
+        /// Records the fields of every event as `name=value` strings
+        #[derive(Default)]
+        struct Capture(Arc<Mutex<Vec<Vec<String>>>>);
+
+        impl Visit for Capture {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0.lock().unwrap().last_mut().unwrap().push(format!("{field}={value:?}"));
+            }
+        }
+
+        impl tracing::Subscriber for Capture {
+            fn register_callsite(&self, _: &'static Metadata<'static>) -> Interest {
+                Interest::sometimes()
+            }
+            fn enabled(&self, _: &Metadata) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event) {
+                self.0.lock().unwrap().push(Vec::new());
+                event.record(&mut Capture(self.0.clone()));
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Capture(events.clone()), || {
+            for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+                assert!(NHI::from_str(nhi_str).is_ok());
+                assert!(NHI::try_from(nhi_str.to_string()).is_ok());
+            }
+            assert!(events.lock().unwrap().is_empty());
+
+            assert!(NHI::from_str("ZZZ0017").is_err());
+            assert!(NHI::try_from("JBX365".to_string()).is_err());
+            assert!(NHI::from_str("ÑBX3656").is_err());
+        });
+        assert_eq!(*events.lock().unwrap(), [
+            ["message=invalid NHI", "reason=\"ChecksumMismatch\"", "length=7"],
+            ["message=invalid NHI", "reason=\"InvalidLength\"", "length=6"],
+            ["message=invalid NHI", "reason=\"NonAscii\"", "length=7"],
+        ]);
     }
 }
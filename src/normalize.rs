@@ -0,0 +1,348 @@
+//! Normalization of user-entered NHI strings prior to validation.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{ParseNhiError, NHI};
+
+/// A single transformation applied while normalizing a potential NHI string
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum NormalizationStep {
+    /// Full-width characters (e.g. `"Ｚ"` or `"７"`), as entered on some East Asian keyboards,
+    /// were mapped to their ASCII equivalents
+    NarrowedFullWidth,
+    /// Leading and/or trailing ASCII whitespace was removed
+    TrimmedWhitespace,
+    /// Interior ASCII whitespace (e.g. `"ZBN 77 VL"`) was removed
+    RemovedWhitespace,
+    /// Hyphens (e.g. `"ZBN-77-VL"`) were removed
+    RemovedHyphen,
+    /// Lowercase ASCII letters were converted to uppercase
+    Uppercased,
+}
+
+/// The normalizer chain, in the order steps are applied
+const CHAIN: [NormalizationStep; 5] = [
+    NormalizationStep::NarrowedFullWidth,
+    NormalizationStep::TrimmedWhitespace,
+    NormalizationStep::RemovedWhitespace,
+    NormalizationStep::RemovedHyphen,
+    NormalizationStep::Uppercased,
+];
+
+impl NormalizationStep {
+    /// Applies this step to the given string, returning `None` if it would make no change
+    fn apply(self, s: &str) -> Option<String> {
+        let result = match self {
+            NormalizationStep::NarrowedFullWidth => s.chars().map(narrow).collect(),
+            NormalizationStep::TrimmedWhitespace => {
+                s.trim_matches(|c: char| c.is_ascii_whitespace()).to_string()
+            }
+            NormalizationStep::RemovedWhitespace => {
+                s.chars().filter(|c| !c.is_ascii_whitespace()).collect()
+            }
+            NormalizationStep::RemovedHyphen => s.chars().filter(|&c| c != '-').collect(),
+            NormalizationStep::Uppercased => s.to_ascii_uppercase(),
+        };
+        if result == s { None } else { Some(result) }
+    }
+}
+
+/// Normalizes a potential NHI string, recording each transformation that was applied
+///
+/// The normalizer chain maps full-width characters to ASCII, trims surrounding whitespace,
+/// removes interior whitespace, removes hyphens and finally uppercases the string. Each step
+/// that changed the string is recorded in the returned log, in the order it was applied. The
+/// log is empty when no changes were needed.
+///
+/// ***Note:*** The normalized string is not validated, use [crate::is_nhi] or parse it to an
+/// [crate::NHI] to check it.
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI string
+///
+/// returns: (String, Vec<NormalizationStep>)
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{normalize_with_log, NormalizationStep};
+///
+/// let (normalized, log) = normalize_with_log(" zbn-77-vl ");
+/// assert_eq!(normalized, "ZBN77VL");
+/// assert_eq!(
+///     log,
+///     vec![
+///         NormalizationStep::TrimmedWhitespace,
+///         NormalizationStep::RemovedHyphen,
+///         NormalizationStep::Uppercased,
+///     ],
+/// );
+///
+/// assert_eq!(normalize_with_log("ZBN77VL"), ("ZBN77VL".to_string(), vec![]));
+/// ```
+pub fn normalize_with_log(s: &str) -> (String, Vec<NormalizationStep>) {
+    let mut normalized = s.to_string();
+    let mut log = Vec::new();
+    for step in CHAIN {
+        if let Some(result) = step.apply(&normalized) {
+            normalized = result;
+            log.push(step);
+        }
+    }
+    (normalized, log)
+}
+
+/// The separators found between the characters of a user-entered NHI, see
+/// [parse_note_formatting]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum InputFormatting {
+    /// No separators (e.g. `"ZBN77VL"`)
+    Bare,
+    /// Whitespace separators only (e.g. `"ZBN 77 VL"`)
+    Spaces,
+    /// Hyphen separators only (e.g. `"ZBN-77-VL"`)
+    Hyphens,
+    /// Both whitespace and hyphen separators (e.g. `"ZBN 77-VL"`)
+    Mixed,
+}
+
+/// Parses a user-entered string to an [NHI] after normalizing it with [normalize_with_log],
+/// noting the separators it contained so it can be re-rendered in the same style
+///
+/// Only separators between characters are noted: surrounding whitespace, letter case and
+/// full-width characters are normalized without affecting the detected [InputFormatting].
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI string
+///
+/// returns: Result<(NHI, InputFormatting), ParseNhiError>
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{parse_note_formatting, InputFormatting};
+///
+/// let (nhi, formatting) = parse_note_formatting(" zbn-77-vl ").unwrap();
+/// assert_eq!(nhi.as_str(), "ZBN77VL");
+/// assert_eq!(formatting, InputFormatting::Hyphens);
+/// ```
+pub fn parse_note_formatting(s: &str) -> Result<(NHI, InputFormatting), ParseNhiError> {
+    let (normalized, log) = normalize_with_log(s);
+    let nhi = normalized.parse()?;
+    let spaces = log.contains(&NormalizationStep::RemovedWhitespace);
+    let hyphens = log.contains(&NormalizationStep::RemovedHyphen);
+    let formatting = match (spaces, hyphens) {
+        (false, false) => InputFormatting::Bare,
+        (true, false) => InputFormatting::Spaces,
+        (false, true) => InputFormatting::Hyphens,
+        (true, true) => InputFormatting::Mixed,
+    };
+    Ok((nhi, formatting))
+}
+
+impl NHI {
+    /// Parses a string to an [NHI] after normalizing it with [normalize_with_log], which maps
+    /// full-width characters to ASCII and removes any ASCII whitespace and hyphens, e.g.
+    /// `"ZBN 77 VL"`, `"zbn-77-vl"` or `"ＺＢＮ７７ＶＬ"`
+    ///
+    /// Only these separators are removed, so any other stray character still fails validation.
+    /// Use [str::parse] for exact matching, or [parse_note_formatting] to also note the
+    /// separators found.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential NHI string
+    ///
+    /// returns: Result<NHI, ParseNhiError>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi = NHI::parse_normalized(" zbn-77 vl").unwrap();
+    /// assert_eq!(nhi.as_str(), "ZBN77VL");
+    /// assert!(NHI::parse_normalized("ZB N7.7VL").is_err());
+    /// ```
+    pub fn parse_normalized(s: &str) -> Result<NHI, ParseNhiError> {
+        normalize_with_log(s).0.parse()
+    }
+
+    /// Parses a string to an [NHI] like [NHI::parse_normalized], reporting which
+    /// normalizations the string needed, e.g. to count the records of an import that required
+    /// case correction
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential NHI string
+    ///
+    /// returns: Result<(NHI, Normalization), ParseNhiError>
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{Normalization, NHI};
+    ///
+    /// let (nhi, normalization) = NHI::parse_reporting("zbn 77 vl").unwrap();
+    /// assert_eq!(nhi.as_str(), "ZBN77VL");
+    /// assert_eq!(
+    ///     normalization,
+    ///     Normalization { changed_case: true, stripped_separators: true, narrowed_full_width: false },
+    /// );
+    /// ```
+    pub fn parse_reporting(s: &str) -> Result<(NHI, Normalization), ParseNhiError> {
+        let (normalized, log) = normalize_with_log(s);
+        let nhi = normalized.parse()?;
+        let normalization = Normalization {
+            changed_case: log.contains(&NormalizationStep::Uppercased),
+            stripped_separators: log.iter().any(|step| {
+                matches!(
+                    step,
+                    NormalizationStep::TrimmedWhitespace
+                        | NormalizationStep::RemovedWhitespace
+                        | NormalizationStep::RemovedHyphen
+                )
+            }),
+            narrowed_full_width: log.contains(&NormalizationStep::NarrowedFullWidth),
+        };
+        Ok((nhi, normalization))
+    }
+}
+
+/// The normalizations a string needed before it was a canonical NHI, see
+/// [NHI::parse_reporting]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub struct Normalization {
+    /// Whether any lowercase letters were uppercased
+    pub changed_case: bool,
+    /// Whether any ASCII whitespace or hyphens were removed
+    pub stripped_separators: bool,
+    /// Whether any full-width characters (e.g. `"Ｚ"` or `"７"`) were mapped to ASCII
+    pub narrowed_full_width: bool,
+}
+
+/// Maps a full-width form of a printable ASCII character (U+FF01 to U+FF5E) or the ideographic
+/// space (U+3000) to its ASCII equivalent, leaving any other character unchanged
+pub(crate) fn narrow(c: char) -> char {
+    match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap(),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CharacterClass;
+    use NormalizationStep::*;
+
+    #[test]
+    fn normalization_logs_each_step_applied_in_order() {
+        let cases = [
+            ("ZBN77VL", "ZBN77VL", vec![]),
+            ("zbn77vl", "ZBN77VL", vec![Uppercased]),
+            ("\tZBN77VL\n", "ZBN77VL", vec![TrimmedWhitespace]),
+            ("ZBN 77 VL", "ZBN77VL", vec![RemovedWhitespace]),
+            ("ZBN-77-VL", "ZBN77VL", vec![RemovedHyphen]),
+            (" zbn 77-vl ", "ZBN77VL", vec![TrimmedWhitespace, RemovedWhitespace, RemovedHyphen, Uppercased]),
+            ("ZB N7.7VL", "ZBN7.7VL", vec![RemovedWhitespace]),
+            ("ＺＢＮ７７ＶＬ", "ZBN77VL", vec![NarrowedFullWidth]),
+            ("\u{3000}ｚｂｎ－７７ＶＬ", "ZBN77VL", vec![NarrowedFullWidth, TrimmedWhitespace, RemovedHyphen, Uppercased]),
+        ];
+        for (input, expected, steps) in cases {
+            assert_eq!(normalize_with_log(input), (expected.to_string(), steps));
+        }
+    }
+
+    #[test]
+    fn full_width_nhis_are_only_accepted_after_normalization() {
+        use crate::tests::{VALID_NEW, VALID_OLD};
+
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let full_width: String = nhi_str
+                .chars()
+                .map(|c| char::from_u32(c as u32 + 0xFEE0).unwrap())
+                .collect();
+            assert_eq!(full_width.parse::<NHI>(), Err(ParseNhiError::NonAscii { index: 0 }));
+            let (normalized, log) = normalize_with_log(&full_width);
+            assert_eq!(log, vec![NarrowedFullWidth]);
+            assert_eq!(normalized.parse::<NHI>().unwrap().as_str(), *nhi_str);
+        }
+    }
+
+    #[test]
+    fn input_formatting_is_noted_from_interior_separators() {
+        let cases = [
+            ("ZBN77VL", InputFormatting::Bare),
+            (" zbn77vl\n", InputFormatting::Bare),
+            ("ZBN 77 VL", InputFormatting::Spaces),
+            ("ZBN\t77VL", InputFormatting::Spaces),
+            ("ZBN-77-VL", InputFormatting::Hyphens),
+            ("ＺＢＮ－７７ＶＬ", InputFormatting::Hyphens),
+            ("ZBN 77-VL", InputFormatting::Mixed),
+        ];
+        for (input, formatting) in cases {
+            let (nhi, noted) = parse_note_formatting(input).unwrap();
+            assert_eq!(nhi.as_str(), "ZBN77VL");
+            assert_eq!(noted, formatting);
+        }
+        assert_eq!(parse_note_formatting("ZZZ-0044"), Err(ParseNhiError::NoValidCheckDigit));
+        assert_eq!(parse_note_formatting("ZBN.77.VL"), Err(ParseNhiError::InvalidLength));
+    }
+
+    #[test]
+    fn separators_are_removed_before_parsing_normalized_nhis() {
+        for input in ["ZBN 77 VL", "zbn-77-vl", "\tZbN-77 vL\n", "Z-B-N-7-7-V-L", "ZBN77VL"] {
+            assert_eq!(NHI::parse_normalized(input).unwrap().as_str(), "ZBN77VL");
+            assert_eq!(input == "ZBN77VL", input.parse::<NHI>().is_ok());
+        }
+        assert_eq!(NHI::parse_normalized("ZB N7.7VL"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(
+            NHI::parse_normalized("ZB N7_7V"),
+            Err(ParseNhiError::UnexpectedCharacter { index: 4, expected: CharacterClass::Digit }),
+        );
+        assert_eq!(NHI::parse_normalized("ＺＢＮ７７ＶＬ").unwrap().as_str(), "ZBN77VL");
+        assert_eq!(NHI::parse_normalized("ｚｂｎ－７７\u{3000}ＶＬ").unwrap().as_str(), "ZBN77VL");
+        assert_eq!(NHI::parse_normalized("ZBÑ77VL"), Err(ParseNhiError::NonAscii { index: 2 }));
+        assert_eq!(NHI::parse_normalized("ZZZ 00 44"), Err(ParseNhiError::NoValidCheckDigit));
+        assert_eq!(NHI::parse_normalized(" - "), Err(ParseNhiError::InvalidLength));
+    }
+
+    #[test]
+    fn normalizations_needed_are_reported() {
+        let cases = [
+            ("ZBN77VL", false, false, false),
+            ("zbn77vl", true, false, false),
+            ("ZbN77vL", true, false, false),
+            ("ZBN 77 VL", false, true, false),
+            ("\tZBN-77-VL\n", false, true, false),
+            (" zbn-77 vl", true, true, false),
+            ("ＺＢＮ７７ＶＬ", false, false, true),
+            ("ｚｂｎ７７ｖｌ\u{3000}", true, true, true),
+            (" ZBN77VL", false, true, false),
+        ];
+        for (input, changed_case, stripped_separators, narrowed_full_width) in cases {
+            let (nhi, normalization) = NHI::parse_reporting(input).unwrap();
+            assert_eq!(nhi.as_str(), "ZBN77VL");
+            let expected = Normalization { changed_case, stripped_separators, narrowed_full_width };
+            assert_eq!(normalization, expected, "{input}");
+        }
+        assert_eq!(NHI::parse_reporting("ZAC5361").unwrap().1, Normalization::default());
+        assert_eq!(NHI::parse_reporting("zzz 0044"), Err(ParseNhiError::NoValidCheckDigit));
+        assert_eq!(NHI::parse_reporting("ZB N7.7VL"), Err(ParseNhiError::InvalidLength));
+    }
+
+    #[test]
+    fn only_full_width_ascii_variants_are_narrowed() {
+        assert_eq!(narrow('\u{FF00}'), '\u{FF00}');
+        assert_eq!(narrow('\u{FF01}'), '!');
+        assert_eq!(narrow('\u{FF5E}'), '~');
+        assert_eq!(narrow('\u{FF5F}'), '\u{FF5F}');
+        assert_eq!(narrow('Ñ'), 'Ñ');
+        assert_eq!(narrow('Z'), 'Z');
+    }
+}
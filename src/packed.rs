@@ -0,0 +1,327 @@
+//! A compact integer encoding of NHI numbers and a binary batch format built on it.
+
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+
+use crate::{infer_check_char, ParseNhiError, NHI};
+
+/// The packed alphabet, in ascending order: the ten digits followed by the 24 legal letters
+pub(crate) const ALPHABET: &[u8; 34] = b"0123456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+
+/// The number of distinct packed values (34 symbols in each of 7 positions)
+const PACKED_LIMIT: u64 = 34u64.pow(7);
+
+/// The number of distinct compact bodies per prefix (two digits, then any of the 34 symbols)
+const COMPACT_BODIES: u32 = 10 * 10 * 34;
+
+/// The number of distinct compact values (every legal prefix with every body)
+const COMPACT_LIMIT: u32 = 24 * 24 * 24 * COMPACT_BODIES;
+
+impl NHI {
+    /// Packs this NHI into a `u64`
+    ///
+    /// Each of the seven characters is treated as a base-34 digit over the alphabet
+    /// `0-9` followed by `A-Z` excluding `I` and `O`, with the first character most significant.
+    /// As all NHIs have seven characters, packed values sort in the same order as the [NHI]s
+    /// themselves, across both formats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let a: NHI = "ZAC5361".parse().unwrap();
+    /// let b: NHI = "ZBN77VL".parse().unwrap();
+    /// assert!(a.to_packed() < b.to_packed());
+    /// assert_eq!(NHI::from_packed(a.to_packed()).unwrap(), a);
+    /// ```
+    pub fn to_packed(&self) -> u64 {
+        self.0.bytes().fold(0, |acc, b| {
+            let symbol = ALPHABET.iter().position(|&a| a == b).unwrap();
+            acc * 34 + symbol as u64
+        })
+    }
+
+    /// Unpacks an NHI from a value produced by [NHI::to_packed]
+    ///
+    /// Returns an error if the value does not encode a valid NHI.
+    pub fn from_packed(packed: u64) -> Result<NHI, ParseNhiError> {
+        if packed >= PACKED_LIMIT {
+            return Err(ParseNhiError::InvalidFormat);
+        }
+        let mut bytes = [0; 7];
+        let mut rest = packed;
+        for byte in bytes.iter_mut().rev() {
+            *byte = ALPHABET[(rest % 34) as usize];
+            rest /= 34;
+        }
+        // The alphabet is ASCII, so the bytes are always valid UTF-8
+        NHI::from_str(core::str::from_utf8(&bytes).unwrap())
+    }
+
+    /// Encodes this NHI as a four-byte key, e.g. for a key-value store
+    ///
+    /// The check digit or check character follows from the first six characters, so only those
+    /// are encoded, as the big-endian `u32`
+    ///
+    /// ```text
+    /// prefix_ordinal * 3400 + (fourth * 10 + fifth) * 34 + sixth
+    /// ```
+    ///
+    /// where `prefix_ordinal` is [NHI::prefix_ordinal], `fourth` and `fifth` are the values of
+    /// the two digits and `sixth` is the position of the sixth character in the alphabet
+    /// `0-9` followed by `A-Z` excluding `I` and `O`. Only the low 26 bits are used.
+    ///
+    /// The format is recoverable, as the sixth character is a digit (`sixth < 10`) only in the
+    /// old format. Keys compare bytewise in the same order as the [NHI]s themselves, across
+    /// both formats.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let a: NHI = "ZAC5361".parse().unwrap();
+    /// let b: NHI = "ZBN77VL".parse().unwrap();
+    /// assert!(a.to_compact() < b.to_compact());
+    /// assert_eq!(NHI::from_compact(&a.to_compact()).unwrap(), a);
+    /// ```
+    pub fn to_compact(&self) -> [u8; 4] {
+        let symbol = |b| ALPHABET.iter().position(|&a| a == b).unwrap() as u32;
+        let bytes = self.0.as_bytes();
+        let body = (symbol(bytes[3]) * 10 + symbol(bytes[4])) * 34 + symbol(bytes[5]);
+        (self.prefix_ordinal() * COMPACT_BODIES + body).to_be_bytes()
+    }
+
+    /// Decodes an NHI from a key produced by [NHI::to_compact], computing its check digit or
+    /// check character
+    ///
+    /// returns: Result<NHI, ParseNhiError>, with [ParseNhiError::InvalidFormat] if the key is
+    /// out of range, or the errors of [infer_check_char] if no NHI has the encoded first six
+    /// characters
+    pub fn from_compact(bytes: &[u8; 4]) -> Result<NHI, ParseNhiError> {
+        let value = u32::from_be_bytes(*bytes);
+        if value >= COMPACT_LIMIT {
+            return Err(ParseNhiError::InvalidFormat);
+        }
+        let (prefix, body) = ((value / COMPACT_BODIES) as usize, (value % COMPACT_BODIES) as usize);
+        let letter = |i| ALPHABET[10 + i];
+        let mut chars = [
+            letter(prefix / 576), letter(prefix / 24 % 24), letter(prefix % 24),
+            ALPHABET[body / 340], ALPHABET[body / 34 % 10], ALPHABET[body % 34], 0,
+        ];
+        // The alphabet is ASCII, so the bytes are always valid UTF-8
+        chars[6] = infer_check_char(core::str::from_utf8(&chars[..6]).unwrap())? as u8;
+        NHI::from_str(core::str::from_utf8(&chars).unwrap())
+    }
+
+    /// Assigns this NHI to one of `num_shards` shards, for partitioning records across storage
+    ///
+    /// The shard is derived from a well-mixed hash of the [packed](NHI::to_packed) value rather
+    /// than the checksum, which is poorly distributed. It is deterministic and stable across
+    /// versions of this crate, and NHIs are spread evenly across shards, which is tested over
+    /// every NHI sharing a sample of prefixes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is `0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZBN77VL".parse().unwrap();
+    /// assert!(nhi.shard(16) < 16);
+    /// assert_eq!(nhi.shard(16), nhi.shard(16));
+    /// ```
+    pub fn shard(&self, num_shards: u32) -> u32 {
+        assert!(num_shards > 0, "num_shards must be positive");
+        (mix(self.to_packed()) % num_shards as u64) as u32
+    }
+}
+
+/// The SplitMix64 finaliser, which maps similar inputs to very different outputs
+pub(crate) fn mix(mut value: u64) -> u64 {
+    value ^= value >> 30;
+    value = value.wrapping_mul(0xbf58476d1ce4e5b9);
+    value ^= value >> 27;
+    value = value.wrapping_mul(0x94d049bb133111eb);
+    value ^ (value >> 31)
+}
+
+/// Writes a batch of NHIs in a compact, length-prefixed binary format
+///
+/// The layout is a little-endian `u64` count of NHIs, followed by each NHI's
+/// [packed](NHI::to_packed) value as a little-endian `u64`, in the order given. A batch of _n_
+/// NHIs therefore occupies exactly `8 * (n + 1)` bytes.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{read_packed_batch, write_packed_batch, NHI};
+///
+/// let nhis: Vec<NHI> = ["ZAC5361", "ZBN77VL"].iter().map(|s| s.parse().unwrap()).collect();
+///
+/// let mut buffer = Vec::new();
+/// write_packed_batch(&mut buffer, &nhis).unwrap();
+/// assert_eq!(buffer.len(), 24);
+/// assert_eq!(read_packed_batch(&mut buffer.as_slice()).unwrap(), nhis);
+/// ```
+#[cfg(feature = "std")]
+pub fn write_packed_batch<W: Write>(w: &mut W, nhis: &[NHI]) -> io::Result<()> {
+    w.write_all(&(nhis.len() as u64).to_le_bytes())?;
+    for nhi in nhis {
+        w.write_all(&nhi.to_packed().to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Reads a batch of NHIs written by [write_packed_batch]. Requires the `std` feature.
+///
+/// Every NHI is validated as it is read. A value that does not encode a valid NHI produces an
+/// error of kind [io::ErrorKind::InvalidData], and a truncated batch an error of kind
+/// [io::ErrorKind::UnexpectedEof].
+#[cfg(feature = "std")]
+pub fn read_packed_batch<R: Read>(r: &mut R) -> io::Result<Vec<NHI>> {
+    let count = read_u64(r)?;
+    // Don't trust the count prefix to size the allocation up front
+    let mut nhis = Vec::with_capacity(count.min(4096) as usize);
+    for _ in 0..count {
+        let nhi = NHI::from_packed(read_u64(r)?)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid packed NHI"))?;
+        nhis.push(nhi);
+    }
+    Ok(nhis)
+}
+
+#[cfg(feature = "std")]
+fn read_u64<R: Read>(r: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    r.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    fn valid_nhis() -> Vec<NHI> {
+        VALID_OLD.iter().chain(VALID_NEW.iter()).map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn packed_values_round_trip_and_preserve_ordering() {
+        let mut nhis = valid_nhis();
+        nhis.sort();
+        for nhi in &nhis {
+            assert_eq!(&NHI::from_packed(nhi.to_packed()).unwrap(), nhi);
+        }
+        for pair in nhis.windows(2) {
+            assert!(pair[0].to_packed() < pair[1].to_packed());
+        }
+    }
+
+    #[test]
+    fn compact_keys_round_trip_and_preserve_ordering() {
+        let mut nhis = valid_nhis();
+        nhis.extend(["AAA0004", "ZZZ99ZZ", "ZZZ9999"].iter().filter_map(|s| s.parse().ok()));
+        nhis.extend((0..).map_while(|i| NHI::from_index_within_prefix("ZAA", i)));
+        nhis.sort();
+        nhis.dedup();
+        for nhi in &nhis {
+            assert_eq!(&NHI::from_compact(&nhi.to_compact()).unwrap(), nhi);
+            assert!(u32::from_be_bytes(nhi.to_compact()) < COMPACT_LIMIT);
+        }
+        for pair in nhis.windows(2) {
+            assert!(pair[0].to_compact() < pair[1].to_compact());
+        }
+        assert_eq!(NHI::from_compact(&[0; 4]).unwrap().as_str(), "AAA0004");
+    }
+
+    #[test]
+    fn invalid_compact_keys_are_rejected() {
+        let invalid: NHI = NHI("ZZZ0044".into());
+        assert_eq!(NHI::from_compact(&invalid.to_compact()), Err(ParseNhiError::NoValidCheckDigit));
+        assert_eq!(NHI::from_compact(&COMPACT_LIMIT.to_be_bytes()), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(NHI::from_compact(&[0xFF; 4]), Err(ParseNhiError::InvalidFormat));
+        assert!(NHI::from_compact(&(COMPACT_LIMIT - 1).to_be_bytes()).is_ok());
+    }
+
+    #[test]
+    fn shards_are_balanced_over_whole_prefixes() {
+        let num_shards = 16;
+        let mut counts = vec![0_u32; num_shards as usize];
+        let mut total = 0;
+        for prefix in ["ZAA", "ZAB", "JBX", "ABC"] {
+            for nhi in (0..).map_while(|i| NHI::from_index_within_prefix(prefix, i)) {
+                let shard = nhi.shard(num_shards);
+                assert_eq!(shard, nhi.shard(num_shards));
+                counts[shard as usize] += 1;
+                total += 1;
+            }
+        }
+        let mean = total / num_shards;
+        for count in counts {
+            assert!(count.abs_diff(mean) < mean / 10, "{count} vs mean {mean}");
+        }
+    }
+
+    #[test]
+    fn a_single_shard_holds_everything() {
+        for nhi in valid_nhis() {
+            assert_eq!(nhi.shard(1), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_shards_panics() {
+        valid_nhis()[0].shard(0);
+    }
+
+    #[test]
+    fn invalid_packed_values_are_rejected() {
+        let invalid: NHI = NHI("ZZZ0044".into());
+        assert!(NHI::from_packed(invalid.to_packed()).is_err());
+        assert!(NHI::from_packed(PACKED_LIMIT).is_err());
+        assert!(NHI::from_packed(u64::MAX).is_err());
+        assert!(NHI::from_packed(0).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn packed_batches_round_trip() {
+        let nhis = valid_nhis();
+        let mut buffer = Vec::new();
+        write_packed_batch(&mut buffer, &nhis).unwrap();
+        assert_eq!(buffer.len(), 8 * (nhis.len() + 1));
+        assert_eq!(&buffer[..8], &(nhis.len() as u64).to_le_bytes());
+        assert_eq!(read_packed_batch(&mut buffer.as_slice()).unwrap(), nhis);
+
+        let mut empty = Vec::new();
+        write_packed_batch(&mut empty, &[]).unwrap();
+        assert!(read_packed_batch(&mut empty.as_slice()).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn reading_a_corrupt_or_truncated_batch_fails() {
+        let mut buffer = Vec::new();
+        write_packed_batch(&mut buffer, &valid_nhis()).unwrap();
+
+        let truncated = &buffer[..buffer.len() - 1];
+        let error = read_packed_batch(&mut &truncated[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut corrupt = buffer.clone();
+        corrupt[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        let error = read_packed_batch(&mut corrupt.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = read_packed_batch(&mut &u64::MAX.to_le_bytes()[..]).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,129 @@
+//! NATO phonetic spellings of NHI numbers, for reading NHIs aloud and capturing them from voice.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{ParseNhiError, NHI};
+
+/// The NATO phonetic alphabet code words, indexed by letter (`A` is `0`)
+const LETTER_WORDS: [&str; 26] = [
+    "Alfa", "Bravo", "Charlie", "Delta", "Echo", "Foxtrot", "Golf", "Hotel", "India", "Juliett",
+    "Kilo", "Lima", "Mike", "November", "Oscar", "Papa", "Quebec", "Romeo", "Sierra", "Tango",
+    "Uniform", "Victor", "Whiskey", "X-ray", "Yankee", "Zulu",
+];
+
+/// The English digit words, indexed by digit
+const DIGIT_WORDS: [&str; 10] = [
+    "Zero", "One", "Two", "Three", "Four", "Five", "Six", "Seven", "Eight", "Nine",
+];
+
+/// Common alternative spellings of code words, accepted by [NHI::from_phonetic]
+const ALIASES: [(&str, char); 5] = [
+    ("alpha", 'A'), ("juliet", 'J'), ("xray", 'X'), ("whisky", 'W'), ("niner", '9'),
+];
+
+impl NHI {
+    /// Spells this NHI out in the NATO phonetic alphabet, e.g. `"Zulu Bravo November Seven
+    /// Seven Victor Lima"`
+    ///
+    /// Letters are spelt with their NATO code words (`Alfa` through to `Zulu`) and digits with
+    /// their English names (`Zero` through to `Nine`), separated by single spaces.
+    /// [NHI::from_phonetic] always parses the result back to this NHI.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(nhi.phonetic(), "Zulu Bravo November Seven Seven Victor Lima");
+    /// ```
+    pub fn phonetic(&self) -> String {
+        self.0
+            .bytes()
+            .map(|b| match b {
+                b'0'..=b'9' => DIGIT_WORDS[(b - b'0') as usize],
+                _ => LETTER_WORDS[(b - b'A') as usize],
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Parses a phonetic spelling of an NHI, as produced by [NHI::phonetic], to an [NHI]
+    ///
+    /// Words are separated by any whitespace and matched case-insensitively. Each word must be
+    /// a NATO code word (`Alfa` through to `Zulu`) or an English digit name (`Zero` through to
+    /// `Nine`). The common spellings `Alpha`, `Juliet`, `Xray`, `Whisky` and `Niner` are also
+    /// accepted. The spelt string is then validated as normal.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential phonetic spelling of an NHI
+    ///
+    /// returns: Result<NHI, ParseNhiError>, with [ParseNhiError::InvalidFormat] if any word is
+    /// not a recognised code word
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi = NHI::from_phonetic("  zulu BRAVO november\nseven seven victor lima ").unwrap();
+    /// assert_eq!(nhi.as_str(), "ZBN77VL");
+    /// ```
+    pub fn from_phonetic(s: &str) -> Result<NHI, ParseNhiError> {
+        s.split_whitespace()
+            .map(word_char)
+            .collect::<Option<String>>()
+            .ok_or(ParseNhiError::InvalidFormat)?
+            .parse()
+    }
+}
+
+/// Returns the character spelt by the given code word, or `None` if it is not a code word
+fn word_char(word: &str) -> Option<char> {
+    let letters = LETTER_WORDS.iter().zip('A'..='Z');
+    let digits = DIGIT_WORDS.iter().zip('0'..='9');
+    letters
+        .chain(digits)
+        .map(|(word, c)| (*word, c))
+        .chain(ALIASES)
+        .find(|(w, _)| w.eq_ignore_ascii_case(word))
+        .map(|(_, c)| c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn phonetic_spellings_round_trip() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let spelling = nhi.phonetic();
+            assert_eq!(spelling.split(' ').count(), 7);
+            assert_eq!(NHI::from_phonetic(&spelling), Ok(nhi.clone()));
+            assert_eq!(NHI::from_phonetic(&spelling.to_uppercase()), Ok(nhi));
+        }
+    }
+
+    #[test]
+    fn aliases_are_accepted() {
+        let nhi = NHI::from_phonetic("Juliet Bravo Xray Three Six Five Six").unwrap();
+        assert_eq!(nhi.as_str(), "JBX3656");
+        let nhi = NHI::from_phonetic("alpha bravo charlie one two three five").unwrap();
+        assert_eq!(nhi.as_str(), "ABC1235");
+    }
+
+    #[test]
+    fn invalid_spellings_are_rejected() {
+        let from_phonetic = NHI::from_phonetic;
+        assert_eq!(from_phonetic(""), Err(ParseNhiError::InvalidLength));
+        assert_eq!(from_phonetic("Zulu Bravo November Seven Seven Victor"), Err(ParseNhiError::InvalidLength));
+        assert_eq!(from_phonetic("Zulu Bravo November Seven Seven Victor Lime"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(from_phonetic("ZuluBravo November Seven Seven Victor Lima"), Err(ParseNhiError::InvalidFormat));
+        assert_eq!(from_phonetic("India Bravo November Seven Seven Victor Lima"), Err(ParseNhiError::ForbiddenLetter { letter: 'I', index: 0 }));
+        assert_eq!(from_phonetic("Zulu Bravo November Seven Seven Victor Alfa"), Err(ParseNhiError::ChecksumMismatch { expected: 'L', found: 'A' }));
+    }
+}
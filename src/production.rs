@@ -0,0 +1,108 @@
+//! A type-level guarantee that an NHI is not reserved for testing.
+
+use core::fmt;
+use core::ops::Deref;
+
+use crate::NHI;
+
+/// A valid NHI that is guaranteed not to be reserved for testing
+///
+/// Functions that must never receive test data can require a `ProductionNhi` rather than an
+/// [NHI], so the check made when parsing cannot be forgotten or confused with a possibly-test
+/// value. A `ProductionNhi` dereferences to its [NHI], and can be converted back with
+/// [ProductionNhi::into_nhi] or [From].
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{ProductionNhi, NHI};
+///
+/// fn admit(nhi: &ProductionNhi) -> &str {
+///     nhi.as_str()
+/// }
+///
+/// let nhi = ProductionNhi::parse("jbx3656").unwrap();
+/// assert_eq!(admit(&nhi), "JBX3656");
+/// assert_eq!(ProductionNhi::parse("ZBN77VL"), None);
+///
+/// let nhi: NHI = nhi.into();
+/// assert!(nhi.is_not_test());
+/// ```
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct ProductionNhi(NHI);
+
+impl ProductionNhi {
+    /// Parses a string to a `ProductionNhi`, returning `None` if it is not a valid NHI or if it
+    /// is reserved for testing
+    pub fn parse(s: &str) -> Option<ProductionNhi> {
+        s.parse::<NHI>().ok()?.try_into().ok()
+    }
+
+    /// Converts this NHI back to an [NHI]
+    pub fn into_nhi(self) -> NHI {
+        self.0
+    }
+}
+
+impl TryFrom<NHI> for ProductionNhi {
+    type Error = NHI;
+
+    /// Wraps an NHI that is not reserved for testing, returning the NHI back as the error if it
+    /// is
+    fn try_from(nhi: NHI) -> Result<Self, Self::Error> {
+        if nhi.is_test() { Err(nhi) } else { Ok(ProductionNhi(nhi)) }
+    }
+}
+
+impl From<ProductionNhi> for NHI {
+    fn from(nhi: ProductionNhi) -> Self {
+        nhi.0
+    }
+}
+
+impl Deref for ProductionNhi {
+    type Target = NHI;
+
+    fn deref(&self) -> &NHI {
+        &self.0
+    }
+}
+
+impl fmt::Display for ProductionNhi {
+    /// Formats this NHI as its underlying NHI value, see [NHI]'s [Display](fmt::Display)
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{INVALID_NEW, INVALID_OLD, VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn only_valid_non_test_nhis_are_production_nhis() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let production = ProductionNhi::parse(&nhi_str.to_lowercase());
+            assert_eq!(production.is_some(), !nhi_str.starts_with('Z'));
+            if let Some(production) = production {
+                assert_eq!(production.to_string(), *nhi_str);
+                assert_eq!(format!("{production:#}"), production.to_grouped());
+                assert_eq!(production.into_nhi().as_str(), *nhi_str);
+            }
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(["", "DAB8233"].iter()) {
+            assert_eq!(ProductionNhi::parse(nhi_str), None);
+        }
+    }
+
+    #[test]
+    fn test_nhis_are_returned_when_conversion_fails() {
+        let test: NHI = "ZBN77VL".parse().unwrap();
+        assert_eq!(ProductionNhi::try_from(test.clone()), Err(test));
+        let real: NHI = "JBX3656".parse().unwrap();
+        let production = ProductionNhi::try_from(real.clone()).unwrap();
+        assert!(production.is_not_test());
+        assert_eq!(NHI::from(production), real);
+    }
+}
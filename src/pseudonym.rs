@@ -0,0 +1,257 @@
+//! Deterministic pseudonymisation of NHI numbers.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "pseudonym")]
+use core::fmt::Write;
+
+#[cfg(feature = "pseudonym")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "pseudonym")]
+use sha2::Sha256;
+
+use crate::packed::mix;
+use crate::{char_code, expected_check_char, LETTERS, NHI};
+
+/// The number of old format bodies in the `Z` block (576 prefixes × 1000 digit triples)
+const OLD_BODIES: u32 = 576 * 1000;
+/// The number of new format bodies in the `Z` block (576 prefixes × 100 digit pairs × 24 letters)
+const NEW_BODIES: u32 = 576 * 100 * 24;
+
+impl NHI {
+    /// Deterministically maps this NHI to a valid, test-reserved NHI of the same format
+    ///
+    /// The mapping is keyed: the same NHI and key always produce the same pseudonym, so
+    /// referential integrity is preserved across a dataset, while a different key produces an
+    /// unrelated mapping. Outputs are always forced into the `Z` test range, so a pseudonym can
+    /// never be mistaken for a real patient's NHI.
+    ///
+    /// Internally, the last five characters of the NHI's six-character body are shuffled by a
+    /// keyed permutation, tweaked by the NHI's first letter, and a `Z` prefix and fresh check
+    /// character are applied. Distinct new format NHIs sharing a first letter therefore never
+    /// share a pseudonym. As the `Z` range is smaller than the whole NHI space, NHIs with
+    /// different first letters may, and old format NHIs occasionally will, share a pseudonym.
+    ///
+    /// ***Note:*** The permutation is not a cryptographic construction. It keeps real values out
+    /// of test datasets but is no substitute for proper de-identification.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "JBX3656".parse().unwrap();
+    /// let pseudonym = nhi.pseudonymize(b"secret");
+    ///
+    /// assert!(pseudonym.is_test());
+    /// assert_eq!(pseudonym, nhi.pseudonymize(b"secret"));
+    /// ```
+    pub fn pseudonymize(&self, key: &[u8]) -> NHI {
+        let bytes = self.0.as_bytes();
+        let is_new = bytes[6].is_ascii_alphabetic();
+        let (size, half_bits) = if is_new { (NEW_BODIES, 11) } else { (OLD_BODIES, 10) };
+        let tweak = bytes[0];
+
+        let start = body_index(&self.0, is_new);
+        let mut index = start;
+        loop {
+            index = permute(index, half_bits, key, tweak);
+            if index < size {
+                if let Some(nhi) = z_block_nhi(index, is_new) {
+                    return nhi;
+                }
+            }
+            if index == start {
+                break;
+            }
+        }
+        // The cycle through the starting body holds no valid NHI, fall back to the next index
+        // that does. This is vanishingly rare but guarantees termination.
+        (1..size)
+            .find_map(|offset| z_block_nhi((start + offset) % size, is_new))
+            .unwrap()
+    }
+}
+
+#[cfg(feature = "pseudonym")]
+impl NHI {
+    /// Hashes this NHI into a stable, non-reversible token, for joining datasets without
+    /// handling the raw identifier. Requires the `pseudonym` feature.
+    ///
+    /// The token is the HMAC-SHA256 of the NHI's canonical uppercase form under the given key,
+    /// so the same NHI and key always produce the same token however the NHI was cased when
+    /// parsed, while a different key produces unrelated tokens. Unlike [NHI::pseudonymize], the
+    /// result is not an NHI.
+    ///
+    /// ***Note:*** Keep the key secret: the NHI space is small enough to hash every NHI, so
+    /// tokens made with a known key are easily reversed. Pseudonymisation is also no substitute
+    /// for proper de-identification governance, as the other fields of a record can still
+    /// identify a patient.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "zbn77vl".parse().unwrap();
+    /// let token = nhi.pseudonym(b"key");
+    /// assert_eq!(token, "ZBN77VL".parse::<NHI>().unwrap().pseudonym(b"key"));
+    /// assert_ne!(token, nhi.pseudonym(b"other key"));
+    /// ```
+    pub fn pseudonym(&self, key: &[u8]) -> [u8; 32] {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+        mac.update(self.as_str().as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Returns the [pseudonym](NHI::pseudonym) of this NHI as 64 lowercase hexadecimal digits.
+    /// Requires the `pseudonym` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// let nhi: NHI = "ZBN77VL".parse().unwrap();
+    /// assert_eq!(nhi.pseudonym_hex(b"key").len(), 64);
+    /// ```
+    pub fn pseudonym_hex(&self, key: &[u8]) -> String {
+        self.pseudonym(key).iter().fold(String::with_capacity(64), |mut hex, b| {
+            write!(hex, "{b:02x}").unwrap();
+            hex
+        })
+    }
+}
+
+/// Returns the index of an NHI's second to sixth characters within the `Z` block bodies
+fn body_index(nhi: &str, is_new: bool) -> u32 {
+    let codes: Vec<u32> = nhi.chars().skip(1).take(5).map(|c| char_code(c).unwrap()).collect();
+    let letters = (codes[0] - 1) * 24 + codes[1] - 1;
+    if is_new {
+        ((letters * 10 + codes[2]) * 10 + codes[3]) * 24 + codes[4] - 1
+    } else {
+        ((letters * 10 + codes[2]) * 10 + codes[3]) * 10 + codes[4]
+    }
+}
+
+/// Returns the valid `Z` block NHI with the given body index, if it has a check character
+fn z_block_nhi(index: u32, is_new: bool) -> Option<NHI> {
+    let letter = |code: u32| LETTERS[code as usize] as char;
+    let digit = |d: u32| char::from_digit(d, 10).unwrap();
+    let (last, rest) = if is_new { (letter(index % 24), index / 24) } else { (digit(index % 10), index / 10) };
+    let body: String = [
+        'Z',
+        letter(rest / 100 / 24),
+        letter(rest / 100 % 24),
+        digit(rest / 10 % 10),
+        digit(rest % 10),
+        last,
+    ].iter().collect();
+    let check = expected_check_char(&body)?;
+    Some(NHI::new_normalized(format!("{body}{check}")))
+}
+
+/// A keyed four-round Feistel permutation over `2 * half_bits`-bit values
+fn permute(value: u32, half_bits: u32, key: &[u8], tweak: u8) -> u32 {
+    let mask = (1 << half_bits) - 1;
+    let (mut left, mut right) = (value >> half_bits, value & mask);
+    for round in 0..4 {
+        let f = round_function(key, tweak, round, right) as u32 & mask;
+        (left, right) = (right, left ^ f);
+    }
+    (left << half_bits) | right
+}
+
+/// FNV-1a over the key and round inputs, followed by the SplitMix64 finaliser
+fn round_function(key: &[u8], tweak: u8, round: u8, half: u32) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &byte in key.iter().chain(&[tweak, round]).chain(&half.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    mix(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn pseudonyms_are_deterministic_valid_test_nhis_of_the_same_format() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let pseudonym = nhi.pseudonymize(b"key");
+            assert!(crate::is_nhi(pseudonym.as_str()));
+            assert!(pseudonym.is_test());
+            assert_eq!(pseudonym.format(), nhi.format());
+            assert_eq!(pseudonym, nhi.pseudonymize(b"key"));
+        }
+    }
+
+    #[test]
+    fn pseudonyms_depend_on_the_key() {
+        let nhis = VALID_OLD.iter().chain(VALID_NEW.iter()).map(|s| s.parse::<NHI>().unwrap());
+        let changed = nhis
+            .filter(|nhi| nhi.pseudonymize(b"one") != nhi.pseudonymize(b"two"))
+            .count();
+        assert!(changed > VALID_OLD.len());
+    }
+
+    #[test]
+    fn new_format_nhis_sharing_a_first_letter_never_collide() {
+        let mut pseudonyms = HashSet::new();
+        for index in 0..NEW_BODIES / 100 {
+            let body: String = z_block_nhi(index, true).unwrap().as_str()[1..6].to_string();
+            let real_body = format!("J{body}");
+            let nhi = NHI::new_normalized(format!("{real_body}{}", expected_check_char(&real_body).unwrap()));
+            assert!(pseudonyms.insert(nhi.pseudonymize(b"key")));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pseudonym")]
+    fn hashed_pseudonyms_are_keyed_hashes_of_the_canonical_form() {
+        let nhi: NHI = "ZBN77VL".parse().unwrap();
+        assert_eq!(
+            nhi.pseudonym_hex(b""),
+            "f191aaedfd862fa23eea9f23c7301ad22a4d477fe86c319e49f9248c7729dc0e",
+        );
+        assert_eq!(
+            nhi.pseudonym_hex(b"salt"),
+            "4945ed2c8ea93f5c4fc352b121013dcb0e822472c678e72b192afe80418bf774",
+        );
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let nhi: NHI = nhi_str.parse().unwrap();
+            let lowercase: NHI = nhi_str.to_lowercase().parse().unwrap();
+            assert_eq!(nhi.pseudonym(b"salt"), nhi.pseudonym(b"salt"));
+            assert_eq!(nhi.pseudonym(b"salt"), lowercase.pseudonym(b"salt"));
+            assert_ne!(nhi.pseudonym(b"salt"), nhi.pseudonym(b"pepper"));
+            assert_ne!(nhi.pseudonym(b"salt"), nhi.pseudonym(b""));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "pseudonym")]
+    fn distinct_nhis_have_distinct_hashed_pseudonyms() {
+        let tokens: HashSet<[u8; 32]> = VALID_OLD.iter().chain(VALID_NEW.iter())
+            .map(|s| s.parse::<NHI>().unwrap().pseudonym(b"salt"))
+            .collect();
+        assert_eq!(tokens.len(), VALID_OLD.len() + VALID_NEW.len());
+    }
+
+    #[test]
+    fn body_indices_round_trip_through_the_z_block() {
+        for is_new in [false, true] {
+            let size = if is_new { NEW_BODIES } else { OLD_BODIES };
+            for index in (0..size).step_by(997) {
+                if let Some(nhi) = z_block_nhi(index, is_new) {
+                    assert_eq!(body_index(nhi.as_str(), is_new), index);
+                }
+            }
+        }
+    }
+}
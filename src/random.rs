@@ -0,0 +1,121 @@
+//! Random generation of valid NHI numbers, for seeding test databases.
+
+use alloc::string::String;
+
+use rand::Rng;
+
+use crate::enumerate::{new_nhi, old_nhi};
+use crate::{NhiFormat, LETTERS, NHI};
+
+impl NHI {
+    /// Generates a random valid NHI of a random format. Requires the `rand` feature.
+    ///
+    /// ***Note:*** The prefix is random, so the result is usually _not_ reserved for testing and
+    /// may even have been assigned to a real person. Use [NHI::random_test] for test data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{is_nhi, NHI};
+    ///
+    /// let nhi = NHI::random(&mut rand::rng());
+    /// assert!(is_nhi(nhi.as_str()));
+    /// ```
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> NHI {
+        let format = if rng.random_bool(0.5) { NhiFormat::Old } else { NhiFormat::New };
+        NHI::random_with_format(rng, format)
+    }
+
+    /// Generates a random valid NHI of the given format. Requires the `rand` feature.
+    ///
+    /// ***Note:*** The prefix is random, so the result is usually _not_ reserved for testing and
+    /// may even have been assigned to a real person. Use [NHI::random_test] for test data.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// let nhi = NHI::random_with_format(&mut rand::rng(), NhiFormat::New);
+    /// assert_eq!(nhi.format(), NhiFormat::New);
+    /// ```
+    pub fn random_with_format<R: Rng + ?Sized>(rng: &mut R, format: NhiFormat) -> NHI {
+        let prefix: String = (0..3).map(|_| random_letter(rng)).collect();
+        random_with_prefix(rng, &prefix, format)
+    }
+
+    /// Generates a random valid NHI of the given format that is reserved for testing, i.e. one
+    /// starting with `Z`. Requires the `rand` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// let nhi = NHI::random_test(&mut rand::rng(), NhiFormat::Old);
+    /// assert!(nhi.is_test());
+    /// assert_eq!(nhi.format(), NhiFormat::Old);
+    /// ```
+    pub fn random_test<R: Rng + ?Sized>(rng: &mut R, format: NhiFormat) -> NHI {
+        let prefix: String = ['Z', random_letter(rng), random_letter(rng)].iter().collect();
+        random_with_prefix(rng, &prefix, format)
+    }
+}
+
+/// Returns a random letter that may appear in an NHI
+fn random_letter<R: Rng + ?Sized>(rng: &mut R) -> char {
+    LETTERS[rng.random_range(0..LETTERS.len())] as char
+}
+
+/// Returns a uniformly random valid NHI of the given format sharing the given legal prefix
+fn random_with_prefix<R: Rng + ?Sized>(rng: &mut R, prefix: &str, format: NhiFormat) -> NHI {
+    match format {
+        // Roughly one in eleven old format bodies has no check digit, so retry until one does
+        NhiFormat::Old => loop {
+            if let Some(nhi) = old_nhi(prefix, rng.random_range(0..1000)) {
+                break nhi;
+            }
+        },
+        NhiFormat::New => new_nhi(prefix, rng.random_range(0..100), rng.random_range(0..24)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+    use crate::is_nhi;
+
+    #[test]
+    fn random_nhis_are_always_valid() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..5000 {
+            let nhi = NHI::random(&mut rng);
+            assert!(is_nhi(nhi.as_str()));
+        }
+    }
+
+    #[test]
+    fn random_nhis_have_the_requested_format() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for format in [NhiFormat::Old, NhiFormat::New] {
+            for _ in 0..2000 {
+                let nhi = NHI::random_with_format(&mut rng, format);
+                assert!(is_nhi(nhi.as_str()));
+                assert_eq!(nhi.format(), format);
+                let test = NHI::random_test(&mut rng, format);
+                assert!(is_nhi(test.as_str()));
+                assert!(test.is_test());
+                assert_eq!(test.format(), format);
+            }
+        }
+    }
+
+    #[test]
+    fn random_prefixes_are_not_only_test_prefixes() {
+        let mut rng = StdRng::seed_from_u64(2);
+        assert!((0..100).any(|_| NHI::random(&mut rng).is_not_test()));
+    }
+}
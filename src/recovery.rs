@@ -0,0 +1,414 @@
+//! Recovery of valid NHI numbers from mistyped or mis-scanned input.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::packed::ALPHABET;
+use crate::{
+    infer_check_char, new_format_positions_valid, normalize_with_log, old_format_positions_valid,
+    NHI,
+};
+
+/// The most interpretations [interpretations] returns
+const MAX_INTERPRETATIONS: usize = 10;
+
+/// Pairs of legal characters that are easily mistaken for one another when read or scanned
+const LOOKALIKES: [(u8, u8); 20] = [
+    (b'0', b'D'),
+    (b'0', b'Q'),
+    (b'D', b'Q'),
+    (b'1', b'L'),
+    (b'1', b'T'),
+    (b'1', b'7'),
+    (b'7', b'T'),
+    (b'2', b'Z'),
+    (b'3', b'8'),
+    (b'4', b'A'),
+    (b'5', b'S'),
+    (b'6', b'G'),
+    (b'8', b'B'),
+    (b'9', b'G'),
+    (b'C', b'G'),
+    (b'E', b'F'),
+    (b'K', b'X'),
+    (b'M', b'N'),
+    (b'P', b'R'),
+    (b'U', b'V'),
+];
+
+/// Returns every valid NHI that can be made by inserting a single character into the given
+/// six-character string
+///
+/// This targets input where exactly one character was dropped, e.g. by a scanner. Every legal
+/// character (the digits and the letters `A-Z` excluding `I` and `O`) is tried at each of the
+/// seven positions. The input is case-insensitive and anything other than six characters yields
+/// no candidates.
+///
+/// The result is sorted, contains no duplicates and is bounded by the 238 (7 × 34) insertions
+/// tried, although far fewer are valid in practice.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::recover_single_deletion;
+///
+/// let candidates = recover_single_deletion("ZBN7VL");
+/// assert!(candidates.iter().any(|nhi| nhi.as_str() == "ZBN77VL"));
+/// ```
+pub fn recover_single_deletion(s: &str) -> Vec<NHI> {
+    let s = s.to_ascii_uppercase();
+    if s.chars().count() != 6 || !s.is_ascii() {
+        return Vec::new();
+    }
+    let mut candidates = BTreeSet::new();
+    for i in 0..=s.len() {
+        for &c in ALPHABET {
+            let mut candidate = s.clone();
+            candidate.insert(i, c as char);
+            if let Ok(nhi) = candidate.parse::<NHI>() {
+                candidates.insert(nhi);
+            }
+        }
+    }
+    candidates.into_iter().collect()
+}
+
+impl NHI {
+    /// Suggests the NHI a string was likely intended as when only its final check digit or check
+    /// character is wrong, e.g. to ask "did you mean ZHW58CV?"
+    ///
+    /// The check character is recomputed from the first six characters with
+    /// [infer_check_char](crate::infer_check_char), so a valid NHI is suggested as itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: a potential NHI string, checked case-insensitively
+    ///
+    /// returns: `None` if the string is not seven characters long, or if its first six
+    /// characters cannot begin any valid NHI, e.g. because they contain a forbidden letter or
+    /// are an old format body with no valid check digit
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::NHI;
+    ///
+    /// assert_eq!(NHI::suggest("zhw58cx").unwrap().as_str(), "ZHW58CV");
+    /// assert_eq!(NHI::suggest("ZAC5369").unwrap().as_str(), "ZAC5361");
+    /// assert_eq!(NHI::suggest("ZIC5369"), None);
+    /// ```
+    pub fn suggest(s: &str) -> Option<NHI> {
+        if s.chars().count() != 7 {
+            return None;
+        }
+        let mut body: String = s.chars().take(6).collect();
+        let check = infer_check_char(&body).ok()?;
+        body.push(check);
+        body.parse().ok()
+    }
+}
+
+/// A valid NHI that a malformed string may have been intended as, see [interpretations]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interpretation {
+    /// The valid NHI
+    pub nhi: NHI,
+    /// The number of transformations needed to turn the string into the NHI
+    pub transformations: usize,
+    /// How likely the NHI is to be the intended value, between `0.0` and `1.0`
+    pub confidence: f64,
+}
+
+/// Returns the valid NHIs a possibly malformed string may have been intended as, most likely
+/// first
+///
+/// The string is first normalized with [normalize_with_log], each step applied (e.g. changing
+/// case or removing separators) counting as one transformation. If the normalized string is a
+/// valid NHI it is the sole interpretation. Otherwise, every valid NHI one single-character fix
+/// away is an interpretation, with the fix counting as one further transformation:
+///
+/// - a substituted character, for seven-character strings
+/// - an inserted character, for six-character strings
+/// - a deleted character, for eight-character strings
+///
+/// Fixes are weighted by how likely the mistake is: substituting a lookalike character (e.g. `0`
+/// for `D`) is likelier than substituting a neighbouring one (e.g. `Y` for `Z`, or `4` for `5`),
+/// which is likelier than substituting any other character of the same kind. Inserting or
+/// deleting a character that repeats its neighbour, i.e. a dropped or doubled keystroke, is
+/// likelier than any other insertion or deletion. Interpretations are ranked by weight, then in
+/// ascending order, and at most ten are returned.
+///
+/// Each interpretation's confidence is `1 / (1 + transformations)`, shared between all
+/// interpretations needing a single-character fix in proportion to their weight. The
+/// confidences of the returned interpretations therefore sum to that of the valid NHI the
+/// normalized string would have been.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::interpretations;
+///
+/// let exact = interpretations(" zbn-77-vl ");
+/// assert_eq!(exact.len(), 1);
+/// assert_eq!(exact[0].nhi.as_str(), "ZBN77VL");
+/// assert_eq!(exact[0].transformations, 3);
+/// assert_eq!(exact[0].confidence, 0.25);
+///
+/// let fixed = interpretations("ZBN77VK");
+/// assert!(fixed.iter().any(|interpretation| interpretation.nhi.as_str() == "ZBN77VL"));
+/// assert!(fixed.len() <= 10);
+/// ```
+pub fn interpretations(s: &str) -> Vec<Interpretation> {
+    let (normalized, log) = normalize_with_log(s);
+    if let Ok(nhi) = normalized.parse() {
+        let transformations = log.len();
+        let confidence = 1.0 / (1 + transformations) as f64;
+        return vec![Interpretation { nhi, transformations, confidence }];
+    }
+    let mut candidates: Vec<_> = single_char_fixes(&normalized).into_iter().collect();
+    candidates.sort_by(|(a, a_weight), (b, b_weight)| b_weight.cmp(a_weight).then(a.cmp(b)));
+    candidates.truncate(MAX_INTERPRETATIONS);
+    let transformations = log.len() + 1;
+    let total: u32 = candidates.iter().map(|(_, weight)| weight).sum();
+    candidates
+        .into_iter()
+        .map(|(nhi, weight)| {
+            let confidence = weight as f64 / ((1 + transformations) as f64 * total as f64);
+            Interpretation { nhi, transformations, confidence }
+        })
+        .collect()
+}
+
+/// Returns every valid NHI one substitution, insertion or deletion away from the given uppercase
+/// string, along with the weight of the likeliest fix producing it
+fn single_char_fixes(s: &str) -> BTreeMap<NHI, u32> {
+    let mut candidates = BTreeMap::new();
+    if !s.is_ascii() {
+        return candidates;
+    }
+    let bytes = s.as_bytes();
+    let mut add = |candidate: String, weight: u32| {
+        if let Ok(nhi) = candidate.parse::<NHI>() {
+            let best = candidates.entry(nhi).or_insert(weight);
+            *best = weight.max(*best);
+        }
+    };
+    match s.len() {
+        6 => {
+            for i in 0..=6 {
+                for &c in ALPHABET {
+                    let repeats = bytes.get(i) == Some(&c) || (i > 0 && bytes[i - 1] == c);
+                    let candidate = format!("{}{}{}", &s[..i], c as char, &s[i..]);
+                    add(candidate, if repeats { 2 } else { 1 });
+                }
+            }
+        }
+        7 => {
+            for i in 0..7 {
+                for &c in ALPHABET {
+                    let candidate = format!("{}{}{}", &s[..i], c as char, &s[i + 1..]);
+                    add(candidate, substitution_weight(bytes[i], c));
+                }
+            }
+        }
+        8 => {
+            for i in 0..8 {
+                let repeats =
+                    bytes.get(i + 1) == Some(&bytes[i]) || (i > 0 && bytes[i - 1] == bytes[i]);
+                let candidate = format!("{}{}", &s[..i], &s[i + 1..]);
+                add(candidate, if repeats { 2 } else { 1 });
+            }
+        }
+        _ => {}
+    }
+    candidates
+}
+
+/// Returns how likely the character `found` is to have been entered in place of `intended`,
+/// from `4` for a lookalike down to `1` for a character of a different kind
+fn substitution_weight(found: u8, intended: u8) -> u32 {
+    if LOOKALIKES.contains(&(found, intended)) || LOOKALIKES.contains(&(intended, found)) {
+        4
+    } else if found.abs_diff(intended) == 1 && found.is_ascii_digit() == intended.is_ascii_digit() {
+        3
+    } else if found.is_ascii_digit() == intended.is_ascii_digit() {
+        2
+    } else {
+        1
+    }
+}
+
+/// Flags a pair of strings that are plausibly the same NHI entry mistyped across the old and new
+/// formats
+///
+/// There is no defined equivalence between old and new format NHIs, but some datasets record the
+/// same entry under lookalike values in both formats. A pair is flagged if, ignoring case, one has
+/// the character pattern of an old format NHI, the other has the character pattern of a new
+/// format NHI, and both share the same three-letter prefix and first two digits. Check digits
+/// and check characters are not considered, as mistyped values rarely have valid ones.
+///
+/// ***Note:*** This is a heuristic for flagging records for data-quality review. It is _not_ an
+/// authoritative identity match and must never be used to merge or link patient records.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::likely_transcription_variants;
+///
+/// assert!(likely_transcription_variants("ZBN7736", "zbn77vl"));
+/// assert!(!likely_transcription_variants("ZBN7736", "ZBN78VL"));
+/// assert!(!likely_transcription_variants("ZAC5361", "ZAC5361"));
+/// ```
+pub fn likely_transcription_variants(a: &str, b: &str) -> bool {
+    let (a, b) = (a.to_ascii_uppercase(), b.to_ascii_uppercase());
+    let crosses_formats = (old_format_positions_valid(&a) && new_format_positions_valid(&b))
+        || (new_format_positions_valid(&a) && old_format_positions_valid(&b));
+    crosses_formats && a[..5] == b[..5]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn any_single_deletion_from_a_valid_nhi_can_be_recovered() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            for i in 0..7 {
+                let mut deleted = nhi_str.to_lowercase();
+                deleted.remove(i);
+                let candidates = recover_single_deletion(&deleted);
+                assert!(candidates.iter().any(|nhi| nhi.as_str() == *nhi_str));
+                assert!(candidates.windows(2).all(|pair| pair[0] < pair[1]));
+                assert!(candidates.len() <= 238);
+            }
+        }
+    }
+
+    #[test]
+    fn only_six_character_ascii_inputs_are_recovered() {
+        for s in ["", "ZBN77", "ZBN77VL", "ZBN77VLX", "ZBÑ7VL"] {
+            assert!(recover_single_deletion(s).is_empty());
+        }
+    }
+
+    #[test]
+    fn wrong_check_characters_are_corrected() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            for &c in ALPHABET.iter().chain(b"-\xC3") {
+                let mut mistyped = nhi_str.to_lowercase();
+                mistyped.replace_range(6..7, &(c as char).to_string());
+                assert_eq!(NHI::suggest(&mistyped).unwrap().as_str(), *nhi_str, "{mistyped}");
+            }
+        }
+    }
+
+    #[test]
+    fn malformed_strings_have_no_suggestion() {
+        let cases = [
+            "", "ZHW58C", "ZHW58CVV", "IHW58CV", "ZHO58CV", "ZH958CV", "ZHW5ACV", "ZHÑ58CV",
+            "ZZZ0044", "DAB8233",
+        ];
+        for s in cases {
+            assert_eq!(NHI::suggest(s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn valid_normalized_strings_are_the_sole_interpretation() {
+        let cases = [("ZBN77VL", 0, 1.0), ("zbn77vl", 1, 0.5), (" ZBN 77-VL", 3, 0.25)];
+        for (input, transformations, confidence) in cases {
+            let found = interpretations(input);
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].nhi.as_str(), "ZBN77VL");
+            assert_eq!(found[0].transformations, transformations);
+            assert_eq!(found[0].confidence, confidence);
+        }
+    }
+
+    #[test]
+    fn single_character_fixes_are_bounded_ranked_interpretations() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let mut substituted = nhi_str.to_string();
+            substituted.replace_range(3..4, if &nhi_str[3..4] == "9" { "8" } else { "9" });
+            let mut inserted = nhi_str.to_string();
+            inserted.insert(2, 'A');
+            let mut deleted = nhi_str.to_string();
+            deleted.remove(4);
+            for input in [substituted, inserted, deleted] {
+                let fixes = single_char_fixes(&input);
+                assert!(fixes.contains_key(&nhi_str.parse::<NHI>().unwrap()), "{input}");
+                let found = interpretations(&input.to_lowercase());
+                assert!(!found.is_empty() && found.len() <= MAX_INTERPRETATIONS);
+                assert!(found.windows(2).all(|pair| pair[0].confidence >= pair[1].confidence));
+                let total: f64 = found.iter().map(|interpretation| interpretation.confidence).sum();
+                assert!((total - 1.0 / 3.0).abs() < 1e-9, "{input}");
+                for interpretation in &found {
+                    assert_eq!(interpretation.transformations, 2);
+                    let weight = fixes[&interpretation.nhi];
+                    assert!(found.iter().all(|other| fixes[&other.nhi] <= weight
+                        || other.confidence > interpretation.confidence));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn likely_fixes_survive_truncation() {
+        let cases = [
+            ("YAC5361", "ZAC5361"),
+            ("YZZ0016", "ZZZ0016"),
+            ("ZAC536L", "ZAC5361"),
+            ("ZBN77VK", "ZBN77VL"),
+            ("ZBN7VL", "ZBN77VL"),
+            ("ZBNN77VL", "ZBN77VL"),
+        ];
+        for (input, intended) in cases {
+            let found = interpretations(input);
+            assert!(
+                found.iter().any(|interpretation| interpretation.nhi.as_str() == intended),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn lookalike_substitutions_outrank_others() {
+        let found = interpretations("ZAC536L");
+        assert_eq!(found[0].nhi.as_str(), "ZAC5361");
+        assert!(found[1..].iter().all(|other| other.confidence < found[0].confidence));
+    }
+
+    #[test]
+    fn unfixable_strings_have_no_interpretations() {
+        for s in ["", "ZBN77", "ZBN77VLXX", "not an NHI", "ZBÑ77VL"] {
+            assert!(interpretations(s).is_empty(), "{s}");
+        }
+    }
+
+    #[test]
+    fn transcription_variants_share_a_prefix_and_digits_across_formats() {
+        assert!(likely_transcription_variants("ZBN7736", "ZBN77VL"));
+        assert!(likely_transcription_variants("zbn77vl", "ZBN7700"));
+        // Check values are not considered
+        assert!(likely_transcription_variants("ZBN7799", "ZBN77AA"));
+    }
+
+    #[test]
+    fn transcription_variants_must_cross_formats_and_match_exactly() {
+        // Same format
+        assert!(!likely_transcription_variants("ZBN77VL", "ZBN77VL"));
+        assert!(!likely_transcription_variants("ZBN7736", "ZBN7736"));
+        // Differing prefix or digits
+        assert!(!likely_transcription_variants("ZBM7736", "ZBN77VL"));
+        assert!(!likely_transcription_variants("ZBN7636", "ZBN77VL"));
+        assert!(!likely_transcription_variants("ZBN7736", "ZBN76VL"));
+        // Not matching either format
+        assert!(!likely_transcription_variants("ZBN77", "ZBN77VL"));
+        assert!(!likely_transcription_variants("ZBN7736", "ZBN77V1"));
+        assert!(!likely_transcription_variants("", ""));
+    }
+}
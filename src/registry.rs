@@ -0,0 +1,103 @@
+//! Checking NHI numbers against caller-provided registries of assigned values.
+
+use alloc::collections::BTreeSet;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+use std::hash::{BuildHasher, Hash};
+
+use crate::NHI;
+
+/// A collection that can report whether it contains a value, such as a local registry of
+/// assigned NHIs
+///
+/// This crate provides no registry data itself. Implement this trait for whatever holds your
+/// registry, e.g. a database client, or use the implementations for `HashSet` (with the `std`
+/// feature) and [BTreeSet].
+pub trait Contains<T> {
+    /// Returns `true` if the collection contains the given value and `false` otherwise
+    fn contains(&self, value: &T) -> bool;
+}
+
+#[cfg(feature = "std")]
+impl<T: Eq + Hash, S: BuildHasher> Contains<T> for HashSet<T, S> {
+    fn contains(&self, value: &T) -> bool {
+        HashSet::contains(self, value)
+    }
+}
+
+impl<T: Ord> Contains<T> for BTreeSet<T> {
+    fn contains(&self, value: &T) -> bool {
+        BTreeSet::contains(self, value)
+    }
+}
+
+/// Checks whether a string is a valid NHI that appears in the given registry of assigned NHIs
+///
+/// The string is validated first, so the registry is only ever queried with valid NHIs.
+///
+/// ***Note:*** This crate provides no registry of its own and knows nothing of which NHIs have
+/// been assigned. The answer is only as good as the registry provided.
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI string
+/// * `registry`: the caller's registry of assigned NHIs
+///
+/// returns: `true` if the string is a valid NHI found in the registry and `false` otherwise
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeSet;
+/// use nhi::{is_assigned_nhi, NHI};
+///
+/// let registry: BTreeSet<NHI> = ["ZAC5361".parse().unwrap()].into_iter().collect();
+/// assert!(is_assigned_nhi("zac5361", &registry));
+/// assert!(!is_assigned_nhi("ZBN77VL", &registry));
+/// ```
+pub fn is_assigned_nhi(s: &str, registry: &impl Contains<NHI>) -> bool {
+    s.parse().is_ok_and(|nhi| registry.contains(&nhi))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    struct CountingRegistry {
+        queries: Cell<usize>,
+    }
+
+    impl Contains<NHI> for CountingRegistry {
+        fn contains(&self, value: &NHI) -> bool {
+            self.queries.set(self.queries.get() + 1);
+            value.as_str() == "ZBN77VL"
+        }
+    }
+
+    #[test]
+    fn custom_registries_are_only_queried_with_valid_nhis() {
+        let registry = CountingRegistry { queries: Cell::new(0) };
+        assert!(is_assigned_nhi("zbn77vl", &registry));
+        assert!(!is_assigned_nhi("ZAC5361", &registry));
+        assert!(!is_assigned_nhi("ZZZ0044", &registry));
+        assert!(!is_assigned_nhi("", &registry));
+        assert_eq!(registry.queries.get(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn sets_can_be_used_as_registries() {
+        let nhis = ["ZAC5361", "JBX3656"].map(|s| s.parse::<NHI>().unwrap());
+        let hash_set: HashSet<NHI> = nhis.iter().cloned().collect();
+        let btree_set: BTreeSet<NHI> = nhis.iter().cloned().collect();
+        for s in ["ZAC5361", "jbx3656"] {
+            assert!(is_assigned_nhi(s, &hash_set));
+            assert!(is_assigned_nhi(s, &btree_set));
+        }
+        assert!(!is_assigned_nhi("ZBN77VL", &hash_set));
+        assert!(!is_assigned_nhi("ZBN77VL", &btree_set));
+    }
+}
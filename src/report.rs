@@ -0,0 +1,288 @@
+//! Structured validation results, ready to be returned from validation services.
+
+use alloc::string::{String, ToString};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{NhiFormat, NHI};
+
+/// The outcome of validating a potential NHI string, see [validation_report]
+///
+/// Serializable with the `serde` feature. The raw input is never held, only a masked copy.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct ValidationReport {
+    /// The input with every character except the first and last replaced by `*`, or entirely
+    /// replaced if it is shorter than three characters
+    pub input_masked: String,
+    /// Whether the input is a valid NHI
+    pub valid: bool,
+    /// The format whose character pattern the input matches, even if its check value is wrong,
+    /// or `None` if it matches neither
+    pub format: Option<NhiFormat>,
+    /// Whether the input is reserved for testing, or `None` if it is not a valid NHI
+    pub is_test: Option<bool>,
+    /// The reason the input is invalid, the name of its [ParseNhiError](crate::ParseNhiError)
+    /// variant (e.g. `"ChecksumMismatch"`) which never includes characters of the input, or
+    /// `None` if it is valid
+    pub reason: Option<String>,
+}
+
+/// Validates a string, producing a structured [ValidationReport] of the outcome
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI string
+///
+/// returns: ValidationReport
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{validation_report, NhiFormat};
+///
+/// let report = validation_report("zbn77vl");
+/// assert!(report.valid);
+/// assert_eq!(report.input_masked, "z*****l");
+/// assert_eq!(report.format, Some(NhiFormat::New));
+/// assert_eq!(report.is_test, Some(true));
+/// assert_eq!(report.reason, None);
+///
+/// let report = validation_report("ZZZ0044");
+/// assert!(!report.valid);
+/// assert_eq!(report.format, Some(NhiFormat::Old));
+/// assert_eq!(report.is_test, None);
+/// assert_eq!(report.reason.as_deref(), Some("NoValidCheckDigit"));
+/// ```
+pub fn validation_report(s: &str) -> ValidationReport {
+    let result = s.parse::<NHI>();
+    ValidationReport {
+        input_masked: mask(s),
+        valid: result.is_ok(),
+        format: NHI::matches_format(s),
+        is_test: result.as_ref().ok().map(NHI::is_test),
+        reason: result.err().map(|error| error.name().to_string()),
+    }
+}
+
+/// An event recording that an NHI was validated, for admitting patient identifiers into an event
+/// store, see [NHI::to_validated_event]
+///
+/// Serializable with the `serde` feature.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NhiValidatedEvent {
+    /// The validated NHI, in its canonical uppercase form
+    pub nhi: NHI,
+    /// The format of the NHI
+    pub format: NhiFormat,
+    /// Whether the NHI is reserved for testing
+    pub is_test: bool,
+    /// When the NHI was validated, e.g. as an RFC 3339 timestamp. This crate never reads the
+    /// clock, so this is `None` until supplied by the caller.
+    pub validated_at: Option<String>,
+}
+
+impl NhiValidatedEvent {
+    /// Sets the time the NHI was validated
+    pub fn with_timestamp(mut self, validated_at: impl Into<String>) -> Self {
+        self.validated_at = Some(validated_at.into());
+        self
+    }
+}
+
+impl NHI {
+    /// Returns an event recording that this NHI was validated, carrying its canonical form,
+    /// format and test status
+    ///
+    /// The event's timestamp is left for the caller to supply, see
+    /// [NhiValidatedEvent::with_timestamp].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nhi::{NhiFormat, NHI};
+    ///
+    /// let nhi: NHI = "zbn77vl".parse().unwrap();
+    /// let event = nhi.to_validated_event().with_timestamp("2024-01-01T00:00:00Z");
+    /// assert_eq!(event.nhi.as_str(), "ZBN77VL");
+    /// assert_eq!(event.format, NhiFormat::New);
+    /// assert!(event.is_test);
+    /// assert_eq!(event.validated_at.as_deref(), Some("2024-01-01T00:00:00Z"));
+    /// ```
+    pub fn to_validated_event(&self) -> NhiValidatedEvent {
+        NhiValidatedEvent {
+            nhi: self.clone(),
+            format: self.format(),
+            is_test: self.is_test(),
+            validated_at: None,
+        }
+    }
+}
+
+/// A three-state, traffic-light style summary of a validation outcome for display in UIs, see
+/// [validation_severity]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Severity {
+    /// The string is a valid NHI that is not reserved for testing
+    Ok,
+    /// The string is a structurally valid NHI, but it is reserved for testing
+    Warning,
+    /// The string is not a valid NHI
+    Error,
+}
+
+/// Validates a string, summarizing the outcome as a [Severity] for display
+///
+/// | Outcome                          | Severity            |
+/// |----------------------------------|---------------------|
+/// | valid, not reserved for testing  | [Severity::Ok]      |
+/// | valid, reserved for testing      | [Severity::Warning] |
+/// | invalid                          | [Severity::Error]   |
+///
+/// [Severity::Warning] specifically flags values that pass the NHI Validation Routine but are
+/// reserved for testing, which should not usually appear in production data.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{validation_severity, Severity};
+///
+/// assert_eq!(validation_severity("JBX3656"), Severity::Ok);
+/// assert_eq!(validation_severity("ZBN77VL"), Severity::Warning);
+/// assert_eq!(validation_severity("ZZZ0044"), Severity::Error);
+/// ```
+pub fn validation_severity(s: &str) -> Severity {
+    match s.parse::<NHI>() {
+        Ok(nhi) if nhi.is_test() => Severity::Warning,
+        Ok(_) => Severity::Ok,
+        Err(_) => Severity::Error,
+    }
+}
+
+/// Masks all but the first and last characters of a string
+fn mask(s: &str) -> String {
+    let len = s.chars().count();
+    if len < 3 {
+        return "*".repeat(len);
+    }
+    s.chars()
+        .enumerate()
+        .map(|(i, c)| if i == 0 || i == len - 1 { c } else { '*' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{INVALID_NEW, INVALID_OLD, VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn reports_describe_valid_nhis() {
+        for (nhi_str, format) in VALID_OLD.iter().map(|s| (s, NhiFormat::Old))
+            .chain(VALID_NEW.iter().map(|s| (s, NhiFormat::New)))
+        {
+            let report = validation_report(nhi_str);
+            assert!(report.valid);
+            assert_eq!(report.format, Some(format));
+            assert_eq!(report.is_test, Some(nhi_str.starts_with('Z')));
+            assert_eq!(report.reason, None);
+        }
+    }
+
+    #[test]
+    fn reports_describe_invalid_strings() {
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()) {
+            let report = validation_report(nhi_str);
+            assert!(!report.valid);
+            assert!(report.format.is_some());
+            assert_eq!(report.is_test, None);
+            assert!(report.reason.is_some());
+        }
+        let report = validation_report("not an NHI");
+        assert_eq!(report.format, None);
+        assert_ne!(report.reason, validation_report("ZZZ0044").reason);
+    }
+
+    #[test]
+    fn reasons_never_include_characters_of_the_input() {
+        let cases = [
+            ("ZZZ0017", "ChecksumMismatch"),
+            ("ZZZ0044", "NoValidCheckDigit"),
+            ("ZZZ004", "InvalidLength"),
+            ("ZZO0017", "ForbiddenLetter"),
+            ("ZZ00017", "UnexpectedCharacter"),
+            ("ZZÿ0017", "NonAscii"),
+            ("!@#$%&*", "UnexpectedCharacter"),
+        ];
+        for (input, reason) in cases {
+            let report = validation_report(input);
+            assert_eq!(report.reason.as_deref(), Some(reason));
+            assert!(!report.reason.unwrap().chars().any(|c| input.contains(c)), "{input}");
+        }
+    }
+
+    #[test]
+    fn severities_combine_validity_and_test_reservation() {
+        for nhi_str in VALID_OLD.iter().chain(VALID_NEW.iter()) {
+            let expected = if nhi_str.starts_with('Z') { Severity::Warning } else { Severity::Ok };
+            assert_eq!(validation_severity(nhi_str), expected);
+            assert_eq!(validation_severity(&nhi_str.to_lowercase()), expected);
+        }
+        for nhi_str in INVALID_OLD.iter().chain(INVALID_NEW.iter()).chain(["", "JBX365"].iter()) {
+            assert_eq!(validation_severity(nhi_str), Severity::Error);
+        }
+    }
+
+    #[test]
+    fn validated_events_describe_the_nhi() {
+        for (nhi_str, format) in VALID_OLD.iter().map(|s| (s, NhiFormat::Old))
+            .chain(VALID_NEW.iter().map(|s| (s, NhiFormat::New)))
+        {
+            let nhi: NHI = nhi_str.to_lowercase().parse().unwrap();
+            let event = nhi.to_validated_event();
+            assert_eq!(event.nhi.as_str(), *nhi_str);
+            assert_eq!(event.format, format);
+            assert_eq!(event.is_test, nhi.is_test());
+            assert_eq!(event.validated_at, None);
+            assert_eq!(event.with_timestamp("now").validated_at.as_deref(), Some("now"));
+        }
+    }
+
+    #[test]
+    fn inputs_are_masked() {
+        assert_eq!(validation_report("ZAC5361").input_masked, "Z*****1");
+        assert_eq!(validation_report("ZÑ").input_masked, "**");
+        assert_eq!(validation_report("").input_masked, "");
+        assert_eq!(validation_report("ÑAC5361X").input_masked, "Ñ******X");
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn reports_serialize_to_json() {
+        let json = serde_json::to_value(validation_report("ZBN77VL")).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "input_masked": "Z*****L",
+            "valid": true,
+            "format": "New",
+            "is_test": true,
+            "reason": null,
+        }));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn validated_events_round_trip_through_json() {
+        let event = "ZAC5361".parse::<NHI>().unwrap().to_validated_event().with_timestamp("t");
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json, serde_json::json!({
+            "nhi": "ZAC5361",
+            "format": "Old",
+            "is_test": true,
+            "validated_at": "t",
+        }));
+        assert_eq!(serde_json::from_value::<NhiValidatedEvent>(json).unwrap(), event);
+    }
+}
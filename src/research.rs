@@ -0,0 +1,144 @@
+//! Non-standard validation helpers for studying the NHI format.
+//!
+//! Requires the `research` feature. Nothing in this module implements the HISO 10046:2023
+//! standard and it must never be used to validate real NHI numbers.
+
+use crate::{new_format_positions_valid, old_format_positions_valid, NhiFormat};
+
+/// Checks a string against a **hypothetical, non-standard** variant of the NHI Validation
+/// Routine in which `I` and `O` are legal letters
+///
+/// Letters are given codes over the full alphabet (`A` is `1` through to `Z` is `26`) rather
+/// than the 24-letter NHI alphabet, and new format check characters are therefore drawn from
+/// `A` through to `W`. Because the letter codes differ, many standard NHIs do _not_ satisfy this
+/// variant.
+///
+/// ***Note:*** This is strictly a research and analysis helper, use [crate::is_nhi] for real
+/// validation.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::is_nhi_permissive_alphabet;
+///
+/// assert!(is_nhi_permissive_alphabet("IOA1232"));
+/// assert!(!is_nhi_permissive_alphabet("IOA1230"));
+/// ```
+pub fn is_nhi_permissive_alphabet(s: &str) -> bool {
+    let nhi = s.to_ascii_uppercase();
+    let bytes = nhi.as_bytes();
+    if bytes.len() != 7
+        || !bytes[..3].iter().all(u8::is_ascii_uppercase)
+        || !bytes[3..5].iter().all(u8::is_ascii_digit)
+    {
+        return false;
+    }
+    let checksum: u32 = bytes[..6].iter()
+        .zip((2..=7).rev())
+        .map(|(&b, weight)| extended_char_code(b) * weight)
+        .sum();
+    if bytes[5..].iter().all(u8::is_ascii_digit) {
+        let checksum = checksum % 11;
+        checksum != 0 && (11 - checksum) % 10 == extended_char_code(bytes[6])
+    } else if bytes[5..].iter().all(u8::is_ascii_uppercase) {
+        23 - checksum % 23 == extended_char_code(bytes[6])
+    } else {
+        false
+    }
+}
+
+/// Checks a string against the NHI Validation Routine with the check character computed by the
+/// given function rather than the HISO 10046:2023 checksum, for experimenting with proposed
+/// revisions of the standard
+///
+/// The string must still be seven characters with the character pattern of one of the formats.
+/// The function is given the uppercase first six characters and their format, and the string is
+/// valid if its final character matches the returned one, ignoring case.
+///
+/// ***Note:*** This bypasses the official checksum and must never be used for real validation,
+/// use [crate::is_nhi] instead.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{compute_check_char, is_nhi_custom_check};
+///
+/// let official = |body: &str, format| compute_check_char(body, format).unwrap_or('?');
+/// assert!(is_nhi_custom_check("ZBN77VL", official));
+/// assert!(is_nhi_custom_check("ZZZ00AA", |_, _| 'A'));
+/// assert!(!is_nhi_custom_check("ZBN77VL", |_, _| 'A'));
+/// ```
+pub fn is_nhi_custom_check(s: &str, check_fn: impl Fn(&str, NhiFormat) -> char) -> bool {
+    let nhi = s.to_ascii_uppercase();
+    let format = if old_format_positions_valid(&nhi) {
+        NhiFormat::Old
+    } else if new_format_positions_valid(&nhi) {
+        NhiFormat::New
+    } else {
+        return false;
+    };
+    let check = check_fn(&nhi[..6], format).to_ascii_uppercase();
+    nhi.ends_with(check)
+}
+
+/// Codes digits by their value and letters by their position in the full alphabet
+fn extended_char_code(b: u8) -> u32 {
+    if b.is_ascii_digit() {
+        (b - b'0') as u32
+    } else {
+        (b - b'A' + 1) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn permissive_alphabet_accepts_i_and_o_with_extended_check_values() {
+        // I=9, O=15, A=1: 9*7 + 15*6 + 1*5 + 1*4 + 2*3 + 3*2 = 174, 174 % 11 = 9, (11 - 9) % 10 = 2
+        assert!(is_nhi_permissive_alphabet("IOA1232"));
+        assert!(is_nhi_permissive_alphabet("ioa1232"));
+        // 174 - 3*2 + 15*2 = 198, 198 % 23 = 14, 23 - 14 = 9 = I
+        assert!(is_nhi_permissive_alphabet("IOA12OI"));
+        for c in "ABCDEFGHJKLMNOPQRSTUVWXYZ".chars() {
+            assert!(!is_nhi_permissive_alphabet(&format!("IOA12O{c}")));
+        }
+    }
+
+    #[test]
+    fn custom_checks_replace_the_official_checksum() {
+        use crate::is_nhi;
+        use crate::tests::{INVALID_NEW, INVALID_OLD, VALID_NEW, VALID_OLD};
+
+        let official = |body: &str, format| crate::compute_check_char(body, format).unwrap_or('?');
+        let all = VALID_OLD.iter()
+            .chain(VALID_NEW.iter())
+            .chain(INVALID_OLD.iter())
+            .chain(INVALID_NEW.iter());
+        for nhi_str in all {
+            assert_eq!(is_nhi_custom_check(&nhi_str.to_lowercase(), official), is_nhi(nhi_str));
+        }
+        let last_digit = |body: &str, format| match format {
+            NhiFormat::Old => body.chars().last().unwrap(),
+            NhiFormat::New => 'z',
+        };
+        assert!(is_nhi_custom_check("ZZZ0044", last_digit));
+        assert!(is_nhi_custom_check("zzz00az", last_digit));
+        assert!(!is_nhi_custom_check("ZZZ0045", last_digit));
+    }
+
+    #[test]
+    fn custom_checks_still_require_a_format() {
+        for s in ["", "ZZZ004", "ZZZ00444", "ZZZ00A4", "IZZ0044", "ZZZ004Ñ"] {
+            assert!(!is_nhi_custom_check(s, |_, _| '4'));
+        }
+    }
+
+    #[test]
+    fn permissive_alphabet_rejects_malformed_strings() {
+        for s in ["", "IOA123", "IOA12322", "IO11232", "IOA1A32", "IOA123I", "IOA12O1", "ÍOA1232"] {
+            assert!(!is_nhi_permissive_alphabet(s));
+        }
+    }
+}
@@ -0,0 +1,313 @@
+//! Set operations over collections of NHI numbers.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::ops::Deref;
+
+use crate::{ParseNhiError, NHI};
+
+/// A sorted set of unique, validated NHIs, e.g. an allow list loaded from configuration
+///
+/// A set can be collected from [NHI]s, or from strings with [NhiSet::try_from_iter], which
+/// reports every invalid string. Collecting parse results into a `Result<NhiSet, _>` instead
+/// stops at the first invalid string. A set dereferences to its `BTreeSet`.
+///
+/// ***Note:*** Collecting strings directly (`FromIterator<&str>` or `FromIterator<String>`) is
+/// deliberately unsupported, as it would have to silently drop invalid strings and an allow
+/// list missing entries fails without warning. Use [NhiSet::try_from_iter], or collect parse
+/// results into a `Result<NhiSet, _>`, so invalid strings are always reported.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{NhiSet, ParseNhiError, NHI};
+///
+/// let set = NhiSet::try_from_iter(["zbn77vl", "ZAC5361", "ZBN77VL"]).unwrap();
+/// assert_eq!(set.len(), 2);
+///
+/// let first: Result<NhiSet, _> = ["ZAC5361", "ZZZ0044"].iter().map(|s| s.parse::<NHI>()).collect();
+/// assert_eq!(first, Err(ParseNhiError::NoValidCheckDigit));
+///
+/// let set: NhiSet = set.into_iter().filter(|nhi| nhi.as_str() != "ZAC5361").collect();
+/// assert_eq!(set.len(), 1);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq, Hash)]
+pub struct NhiSet(BTreeSet<NHI>);
+
+impl NhiSet {
+    /// Validates many potential NHIs, collecting them into a set if all of them are valid
+    ///
+    /// # Arguments
+    ///
+    /// * `iter`: the potential NHI strings
+    ///
+    /// returns: the set of NHIs, or the index and [ParseNhiError] of every invalid item, in
+    /// ascending order of index
+    pub fn try_from_iter<I: IntoIterator<Item = S>, S: AsRef<str>>(
+        iter: I,
+    ) -> Result<NhiSet, Vec<(usize, ParseNhiError)>> {
+        let mut set = BTreeSet::new();
+        let mut invalid = Vec::new();
+        for (i, s) in iter.into_iter().enumerate() {
+            match s.as_ref().parse() {
+                Ok(nhi) => {
+                    set.insert(nhi);
+                }
+                Err(error) => invalid.push((i, error)),
+            }
+        }
+        if invalid.is_empty() { Ok(NhiSet(set)) } else { Err(invalid) }
+    }
+
+    /// Converts this set back to a `BTreeSet`
+    pub fn into_inner(self) -> BTreeSet<NHI> {
+        self.0
+    }
+}
+
+impl FromIterator<NHI> for NhiSet {
+    fn from_iter<I: IntoIterator<Item = NHI>>(iter: I) -> Self {
+        NhiSet(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for NhiSet {
+    type Item = NHI;
+    type IntoIter = alloc::collections::btree_set::IntoIter<NHI>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl From<NhiSet> for BTreeSet<NHI> {
+    fn from(set: NhiSet) -> Self {
+        set.0
+    }
+}
+
+impl Deref for NhiSet {
+    type Target = BTreeSet<NHI>;
+
+    fn deref(&self) -> &BTreeSet<NHI> {
+        &self.0
+    }
+}
+
+/// Returns the NHIs in `a` that are not in `b`
+///
+/// The result is sorted in ascending order and contains no duplicates.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{difference, NHI};
+///
+/// let parse = |s: &[&str]| s.iter().map(|s| s.parse().unwrap()).collect::<Vec<NHI>>();
+/// let a = parse(&["ZBN77VL", "ZAC5361", "JBX3656"]);
+/// let b = parse(&["ZAC5361"]);
+/// assert_eq!(difference(&a, &b), parse(&["JBX3656", "ZBN77VL"]));
+/// ```
+pub fn difference(a: &[NHI], b: &[NHI]) -> Vec<NHI> {
+    merge(a, b, true, false, false)
+}
+
+/// Returns the NHIs in both `a` and `b`
+///
+/// The result is sorted in ascending order and contains no duplicates.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{intersection, NHI};
+///
+/// let parse = |s: &[&str]| s.iter().map(|s| s.parse().unwrap()).collect::<Vec<NHI>>();
+/// let a = parse(&["ZBN77VL", "ZAC5361", "JBX3656"]);
+/// let b = parse(&["ZAC5361", "ABC1235"]);
+/// assert_eq!(intersection(&a, &b), parse(&["ZAC5361"]));
+/// ```
+pub fn intersection(a: &[NHI], b: &[NHI]) -> Vec<NHI> {
+    merge(a, b, false, true, false)
+}
+
+/// Returns the NHIs in exactly one of `a` and `b`
+///
+/// The result is sorted in ascending order and contains no duplicates.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{symmetric_difference, NHI};
+///
+/// let parse = |s: &[&str]| s.iter().map(|s| s.parse().unwrap()).collect::<Vec<NHI>>();
+/// let a = parse(&["ZBN77VL", "ZAC5361"]);
+/// let b = parse(&["ZAC5361", "ABC1235"]);
+/// assert_eq!(symmetric_difference(&a, &b), parse(&["ABC1235", "ZBN77VL"]));
+/// ```
+pub fn symmetric_difference(a: &[NHI], b: &[NHI]) -> Vec<NHI> {
+    merge(a, b, true, false, true)
+}
+
+/// Returns how many leading characters of `target` are needed to distinguish it from every NHI in
+/// `others`, so the remaining characters can be masked on screen
+///
+/// This is one more than the longest prefix `target` shares with any of `others`, and `0` if
+/// `others` is empty. Occurrences of `target` itself in `others` are ignored, as no number of
+/// characters can distinguish an NHI from a duplicate of itself.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{minimal_unique_prefix, NHI};
+///
+/// let parse = |s: &[&str]| s.iter().map(|s| s.parse().unwrap()).collect::<Vec<NHI>>();
+/// let cohort = parse(&["ZAC5361", "ZAA0105", "ZBN77VL", "JBX3656"]);
+/// assert_eq!(minimal_unique_prefix(&cohort[0], &cohort), 3);
+/// assert_eq!(minimal_unique_prefix(&cohort[3], &cohort), 1);
+/// ```
+pub fn minimal_unique_prefix(target: &NHI, others: &[NHI]) -> usize {
+    others
+        .iter()
+        .filter(|other| *other != target)
+        .map(|other| {
+            let pairs = target.as_str().bytes().zip(other.as_str().bytes());
+            pairs.take_while(|(a, b)| a == b).count() + 1
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Merges the sorted, packed forms of `a` and `b`, keeping values found only in `a`, in both,
+/// or only in `b` as requested
+fn merge(a: &[NHI], b: &[NHI], only_a: bool, both: bool, only_b: bool) -> Vec<NHI> {
+    let a = packed_set(a);
+    let b = packed_set(b);
+    let (mut i, mut j) = (0, 0);
+    let mut result = Vec::new();
+    while i < a.len() || j < b.len() {
+        let ordering = match (a.get(i), b.get(j)) {
+            (Some(x), Some(y)) => x.0.cmp(&y.0),
+            (Some(_), None) => Ordering::Less,
+            _ => Ordering::Greater,
+        };
+        match ordering {
+            Ordering::Less => {
+                if only_a { result.push(a[i].1.clone()) }
+                i += 1;
+            }
+            Ordering::Equal => {
+                if both { result.push(a[i].1.clone()) }
+                i += 1;
+                j += 1;
+            }
+            Ordering::Greater => {
+                if only_b { result.push(b[j].1.clone()) }
+                j += 1;
+            }
+        }
+    }
+    result
+}
+
+/// Returns the distinct NHIs paired with their packed values, sorted by packed value
+fn packed_set(nhis: &[NHI]) -> Vec<(u64, &NHI)> {
+    let mut packed: Vec<_> = nhis.iter().map(|nhi| (nhi.to_packed(), nhi)).collect();
+    packed.sort_unstable_by_key(|(p, _)| *p);
+    packed.dedup_by_key(|(p, _)| *p);
+    packed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn set_operations_match_btree_set() {
+        let a: Vec<NHI> = VALID_OLD.iter().chain(VALID_NEW[..5].iter())
+            .chain(VALID_OLD[..3].iter())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let b: Vec<NHI> = VALID_NEW.iter().chain(VALID_OLD[10..].iter())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let set_a: BTreeSet<_> = a.iter().cloned().collect();
+        let set_b: BTreeSet<_> = b.iter().cloned().collect();
+        assert_eq!(difference(&a, &b), set_a.difference(&set_b).cloned().collect::<Vec<_>>());
+        assert_eq!(intersection(&a, &b), set_a.intersection(&set_b).cloned().collect::<Vec<_>>());
+        assert_eq!(
+            symmetric_difference(&a, &b),
+            set_a.symmetric_difference(&set_b).cloned().collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn minimal_unique_prefixes_distinguish_a_target_from_all_others() {
+        let nhis: Vec<NHI> = VALID_OLD.iter().chain(VALID_NEW.iter())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        for target in &nhis {
+            let len = minimal_unique_prefix(target, &nhis);
+            assert!((1..=7).contains(&len));
+            let prefix = &target.as_str()[..len];
+            let mut matching = nhis.iter().filter(|nhi| nhi.as_str().starts_with(prefix));
+            assert!(matching.all(|nhi| nhi == target));
+            let shorter = &target.as_str()[..len - 1];
+            assert!(nhis.iter().any(|nhi| nhi != target && nhi.as_str().starts_with(shorter)));
+        }
+    }
+
+    #[test]
+    fn minimal_unique_prefixes_ignore_duplicates_of_the_target() {
+        let parse = |s: &str| s.parse::<NHI>().unwrap();
+        let target = parse("ZAA0105");
+        assert_eq!(minimal_unique_prefix(&target, &[]), 0);
+        assert_eq!(minimal_unique_prefix(&target, &[target.clone(), target.clone()]), 0);
+        assert_eq!(minimal_unique_prefix(&target, &[target.clone(), parse("ZAA0113")]), 6);
+        assert_eq!(minimal_unique_prefix(&target, &[parse("ZAA0113"), parse("ZAC5361")]), 6);
+    }
+
+    #[test]
+    fn sets_are_collected_from_valid_strings() {
+        let strs: Vec<String> = VALID_OLD.iter().chain(VALID_NEW.iter())
+            .flat_map(|s| [s.to_string(), s.to_lowercase()])
+            .collect();
+        let expected: BTreeSet<NHI> = strs.iter().map(|s| s.parse().unwrap()).collect();
+        assert_eq!(expected.len(), VALID_OLD.len() + VALID_NEW.len());
+        let set = NhiSet::try_from_iter(&strs).unwrap();
+        assert_eq!(*set, expected);
+        assert_eq!(expected.iter().cloned().collect::<NhiSet>(), set);
+        let parsed: Result<NhiSet, _> = strs.iter().map(|s| s.parse::<NHI>()).collect();
+        assert_eq!(parsed, Ok(set.clone()));
+        assert_eq!(set.into_inner(), expected);
+        assert_eq!(NhiSet::try_from_iter(Vec::<&str>::new()), Ok(NhiSet::default()));
+    }
+
+    #[test]
+    fn every_invalid_string_is_reported() {
+        let strs = ["ZAC5361", "ZZZ0044", "zbn77vl", "", "IBN77VL", "ZBN77VL", "ZBN77VK"];
+        assert_eq!(NhiSet::try_from_iter(strs), Err(vec![
+            (1, ParseNhiError::NoValidCheckDigit),
+            (3, ParseNhiError::InvalidLength),
+            (4, ParseNhiError::ForbiddenLetter { letter: 'I', index: 0 }),
+            (6, ParseNhiError::ChecksumMismatch { expected: 'L', found: 'K' }),
+        ]));
+        let parsed: Result<NhiSet, _> = strs.iter().map(|s| s.parse::<NHI>()).collect();
+        assert_eq!(parsed, Err(ParseNhiError::NoValidCheckDigit));
+        let valid: NhiSet = strs.iter().filter_map(|s| s.parse::<NHI>().ok()).collect();
+        let expected = ["ZAC5361", "ZBN77VL"].map(|s| s.parse::<NHI>().unwrap());
+        assert_eq!(valid.into_iter().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn set_operations_handle_empty_inputs() {
+        let a: Vec<NHI> = VALID_OLD.iter().map(|s| s.parse().unwrap()).collect();
+        let mut sorted = a.clone();
+        sorted.sort();
+        assert_eq!(difference(&a, &[]), sorted);
+        assert!(difference(&[], &a).is_empty());
+        assert!(intersection(&a, &[]).is_empty());
+        assert_eq!(symmetric_difference(&[], &a), sorted);
+    }
+}
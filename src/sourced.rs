@@ -0,0 +1,130 @@
+//! Provenance tracking for validated values.
+
+use alloc::string::String;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+
+use crate::{ParseNhiError, NHI};
+
+/// A value tagged with a label describing where it came from (e.g. a source system or file)
+///
+/// The source is metadata only: equality, ordering and hashing of a `Sourced` value consider the
+/// wrapped value alone, so the same NHI from two different sources compares equal.
+#[derive(Debug, Clone)]
+pub struct Sourced<T> {
+    /// The wrapped value
+    pub value: T,
+    /// A label describing where the value came from
+    pub source: String,
+}
+
+impl<T> Sourced<T> {
+    /// Wraps a value with its source label
+    pub fn new(value: T, source: impl Into<String>) -> Self {
+        Sourced { value, source: source.into() }
+    }
+
+    /// Discards the source label, returning the wrapped value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Sourced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: PartialEq> PartialEq for Sourced<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T: Eq> Eq for Sourced<T> {}
+
+impl<T: PartialOrd> PartialOrd for Sourced<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<T: Ord> Ord for Sourced<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T: Hash> Hash for Sourced<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state)
+    }
+}
+
+/// Parses a string to an [NHI], tagging it with the given source label
+///
+/// # Arguments
+///
+/// * `s`: a potential NHI string
+/// * `source`: a label describing where the string came from
+///
+/// returns: Result<Sourced<NHI>, ParseNhiError>
+///
+/// # Examples
+///
+/// ```
+/// use nhi::parse_sourced;
+///
+/// let nhi = parse_sourced("zbn77vl", "lab-results.csv").unwrap();
+/// assert_eq!(nhi.as_str(), "ZBN77VL");
+/// assert_eq!(nhi.source, "lab-results.csv");
+/// assert_eq!(nhi, parse_sourced("ZBN77VL", "pas").unwrap());
+/// ```
+pub fn parse_sourced(s: &str, source: impl Into<String>) -> Result<Sourced<NHI>, ParseNhiError> {
+    Ok(Sourced::new(s.parse()?, source))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn sourced_nhis_carry_their_source() {
+        let nhi = parse_sourced("zac5361", String::from("pas")).unwrap();
+        assert_eq!(nhi.source, "pas");
+        assert!(nhi.is_test());
+        assert_eq!(nhi.into_inner().as_str(), "ZAC5361");
+        assert_eq!(parse_sourced("ZZZ0044", "pas"), Err(ParseNhiError::NoValidCheckDigit));
+    }
+
+    #[test]
+    fn sources_do_not_affect_equality_or_hashing() {
+        let a = parse_sourced("ZAC5361", "a").unwrap();
+        let b = parse_sourced("zac5361", "b").unwrap();
+        let c = parse_sourced("ZBN77VL", "a").unwrap();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        let set: HashSet<_> = [a, b, c].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn sources_do_not_affect_ordering() {
+        let mut nhis = [
+            parse_sourced("ZBN77VL", "a").unwrap(),
+            parse_sourced("ZAC5361", "z").unwrap(),
+            parse_sourced("zac5361", "a").unwrap(),
+        ];
+        nhis.sort();
+        let sorted: Vec<_> = nhis.iter().map(|nhi| (nhi.as_str(), nhi.source.as_str())).collect();
+        assert_eq!(sorted, [("ZAC5361", "z"), ("ZAC5361", "a"), ("ZBN77VL", "a")]);
+        assert_eq!(nhis[0].cmp(&nhis[1]), Ordering::Equal);
+        assert!(nhis[1] < nhis[2]);
+    }
+}
@@ -0,0 +1,100 @@
+//! Binding NHI numbers to and reading them from SQL queries with `sqlx`.
+//!
+//! NHIs are stored as text in their canonical uppercase form, and every value read from the
+//! database is validated, so a malformed stored value produces a decode error.
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::{Database, Decode, Encode, Type};
+
+use crate::NHI;
+
+impl<DB: Database> Type<DB> for NHI
+where
+    String: Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for NHI
+where
+    String: Encode<'q, DB>,
+{
+    /// Encodes this NHI as its underlying string
+    fn encode_by_ref(&self, buf: &mut DB::ArgumentBuffer<'q>) -> Result<IsNull, BoxDynError> {
+        self.as_str().to_string().encode(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for NHI
+where
+    String: Decode<'r, DB>,
+{
+    /// Decodes a string and parses it to an [NHI], failing if it is not a valid NHI
+    fn decode(value: DB::ValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(NHI::try_from(String::decode(value)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::{Row, SqlitePool};
+
+    use super::*;
+    use crate::ParseNhiError;
+
+    fn run<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(future)
+    }
+
+    async fn patients() -> SqlitePool {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+        sqlx::query("CREATE TABLE patient (id INTEGER PRIMARY KEY, nhi TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        pool
+    }
+
+    #[test]
+    fn nhis_round_trip_through_the_database() {
+        run(async {
+            let pool = patients().await;
+            let nhi: NHI = "zbn77vl".parse().unwrap();
+            sqlx::query("INSERT INTO patient (id, nhi) VALUES (1, ?)")
+                .bind(&nhi)
+                .execute(&pool)
+                .await
+                .unwrap();
+            let stored: String = sqlx::query_scalar("SELECT nhi FROM patient").fetch_one(&pool).await.unwrap();
+            assert_eq!(stored, "ZBN77VL");
+            let read: NHI = sqlx::query_scalar("SELECT nhi FROM patient").fetch_one(&pool).await.unwrap();
+            assert_eq!(read, nhi);
+        });
+    }
+
+    #[test]
+    fn malformed_stored_values_fail_to_decode() {
+        run(async {
+            let pool = patients().await;
+            sqlx::query("INSERT INTO patient (id, nhi) VALUES (1, 'ZZZ0044'), (2, 'zbn77vl')")
+                .execute(&pool)
+                .await
+                .unwrap();
+            let rows = sqlx::query("SELECT nhi FROM patient ORDER BY id").fetch_all(&pool).await.unwrap();
+            let error = rows[0].try_get::<NHI, _>("nhi").unwrap_err();
+            let sqlx::Error::ColumnDecode { source, .. } = error else {
+                panic!("expected a column decode error, found {error}");
+            };
+            assert_eq!(source.downcast_ref(), Some(&ParseNhiError::NoValidCheckDigit));
+            assert_eq!(rows[1].try_get::<NHI, _>("nhi").unwrap().as_str(), "ZBN77VL");
+        });
+    }
+}
@@ -0,0 +1,141 @@
+//! Aggregate validation statistics for long-running ingestion.
+
+use alloc::vec::Vec;
+
+use crate::{Rule, NHI};
+
+/// Tallies the outcomes of validating many potential NHI strings
+///
+/// Each recorded string is counted as either valid or invalid, and invalid strings are further
+/// tallied by the validation [Rule] they break:
+///
+/// - [Rule::Length]: the string is not seven characters long
+/// - [Rule::CharacterSet]: the string does not match the character pattern of either format
+/// - [Rule::CheckDigit]: the check digit or check character is wrong
+///
+/// Only counts are kept, the recorded strings themselves are never stored.
+///
+/// # Examples
+///
+/// ```
+/// use nhi::{NhiStats, Rule};
+///
+/// let mut stats = NhiStats::new();
+/// for s in ["ZAC5361", "ZZZ0044", "ZAC536", "ZBN77V"] {
+///     stats.record(s);
+/// }
+///
+/// let summary = stats.summary();
+/// assert_eq!(summary.total, 4);
+/// assert_eq!(summary.valid, 1);
+/// assert_eq!(summary.count(Rule::Length), 2);
+/// assert_eq!(summary.by_rule[0], (Rule::Length, 2));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NhiStats {
+    valid: usize,
+    by_rule: Vec<(Rule, usize)>,
+}
+
+impl NhiStats {
+    /// Creates an accumulator with no recorded outcomes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validates the given string and tallies the outcome
+    pub fn record(&mut self, s: &str) {
+        match s.parse::<NHI>() {
+            Ok(_) => self.valid += 1,
+            Err(error) => {
+                let rule = error.rule();
+                match self.by_rule.iter_mut().find(|(r, _)| *r == rule) {
+                    Some(entry) => entry.1 += 1,
+                    None => self.by_rule.push((rule, 1)),
+                }
+            }
+        }
+    }
+
+    /// Returns the counts of all outcomes recorded so far
+    pub fn summary(&self) -> NhiSummary {
+        let mut by_rule = self.by_rule.clone();
+        by_rule.sort_by(|(_, a), (_, b)| b.cmp(a));
+        let invalid = by_rule.iter().map(|(_, count)| count).sum();
+        NhiSummary {
+            total: self.valid + invalid,
+            valid: self.valid,
+            invalid,
+            by_rule,
+        }
+    }
+}
+
+/// A snapshot of the counts tallied by an [NhiStats] accumulator
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct NhiSummary {
+    /// The number of strings recorded
+    pub total: usize,
+    /// The number of recorded strings that were valid NHIs
+    pub valid: usize,
+    /// The number of recorded strings that were not valid NHIs
+    pub invalid: usize,
+    /// The number of invalid strings breaking each rule, most common first. Rules that no
+    /// recorded string broke are omitted.
+    pub by_rule: Vec<(Rule, usize)>,
+}
+
+impl NhiSummary {
+    /// Returns the number of recorded strings that broke the given rule
+    pub fn count(&self, rule: Rule) -> usize {
+        self.by_rule.iter().find(|(r, _)| *r == rule).map_or(0, |(_, count)| *count)
+    }
+
+    /// Returns the fraction of invalid strings that broke the given rule, between `0.0` and
+    /// `1.0`, or `0.0` if no invalid strings were recorded
+    pub fn failure_share(&self, rule: Rule) -> f64 {
+        if self.invalid == 0 {
+            0.0
+        } else {
+            self.count(rule) as f64 / self.invalid as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{INVALID_NEW, INVALID_OLD, RANDOM_STRINGS, VALID_NEW, VALID_OLD};
+
+    #[test]
+    fn outcomes_are_tallied_by_rule() {
+        let mut stats = NhiStats::new();
+        let all = VALID_OLD.iter()
+            .chain(VALID_NEW.iter())
+            .chain(INVALID_OLD.iter())
+            .chain(INVALID_NEW.iter())
+            .chain(RANDOM_STRINGS.iter());
+        for s in all {
+            stats.record(s);
+        }
+        let summary = stats.summary();
+        assert_eq!(summary.valid, VALID_OLD.len() + VALID_NEW.len());
+        assert_eq!(summary.invalid, INVALID_OLD.len() + INVALID_NEW.len() + RANDOM_STRINGS.len());
+        assert_eq!(summary.total, summary.valid + summary.invalid);
+        assert_eq!(summary.count(Rule::CheckDigit), INVALID_OLD.len() + INVALID_NEW.len());
+        assert_eq!(summary.count(Rule::Length), 4);
+        assert_eq!(summary.count(Rule::CharacterSet), 3);
+        assert_eq!(summary.by_rule[0].0, Rule::CheckDigit);
+        assert!(summary.by_rule.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+        assert!((summary.failure_share(Rule::Length) - 4.0 / 14.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_stats_have_no_failures() {
+        let summary = NhiStats::new().summary();
+        assert_eq!(summary.total, 0);
+        assert!(summary.by_rule.is_empty());
+        assert_eq!(summary.count(Rule::Length), 0);
+        assert_eq!(summary.failure_share(Rule::Length), 0.0);
+    }
+}
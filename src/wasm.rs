@@ -0,0 +1,61 @@
+//! JavaScript bindings to NHI validation, for running the same checks client-side.
+//!
+//! Build with e.g. `wasm-pack build --features wasm`. Each binding is a thin wrapper over the
+//! corresponding Rust function.
+
+use alloc::string::String;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::NHI;
+
+/// Checks a string with [is_nhi](crate::is_nhi), exported to JavaScript as `is_nhi`. Requires
+/// the `wasm` feature.
+#[wasm_bindgen(js_name = is_nhi)]
+pub fn is_nhi_js(value: &str) -> bool {
+    crate::is_nhi(value)
+}
+
+/// Parses a string to its canonical uppercase NHI, exported to JavaScript as `parse_nhi`.
+/// Requires the `wasm` feature.
+///
+/// returns: the uppercase NHI, or `undefined` in JavaScript if the string is not a valid NHI
+#[wasm_bindgen]
+pub fn parse_nhi(value: &str) -> Option<String> {
+    value.parse().ok().map(NHI::into_string)
+}
+
+/// Checks whether a string is a valid NHI reserved for testing, see [NHI::is_test], exported
+/// to JavaScript as `is_test_nhi`. Requires the `wasm` feature.
+///
+/// returns: `true` if the string is a valid NHI reserved for testing, and `false` if it is
+/// not reserved for testing or is not a valid NHI
+#[wasm_bindgen]
+pub fn is_test_nhi(value: &str) -> bool {
+    value.parse().is_ok_and(|nhi: NHI| nhi.is_test())
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::*;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn valid_nhis_are_accepted() {
+        assert!(is_nhi_js("zbn77vl"));
+        assert_eq!(parse_nhi("zbn77vl").as_deref(), Some("ZBN77VL"));
+        assert!(is_test_nhi("zbn77vl"));
+        assert!(!is_test_nhi("JBX3656"));
+    }
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn invalid_nhis_are_rejected() {
+        assert!(!is_nhi_js("ZZZ0044"));
+        assert_eq!(parse_nhi("ZZZ0044"), None);
+        assert!(!is_test_nhi("ZZZ0044"));
+    }
+}
@@ -0,0 +1,11 @@
+#[test]
+fn nhi_numbers_are_validated_at_compile_time() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/valid_literals.rs");
+    t.compile_fail("tests/ui/invalid_check_digit.rs");
+    t.compile_fail("tests/ui/invalid_check_character.rs");
+    t.compile_fail("tests/ui/invalid_format.rs");
+    t.pass("tests/ui/const_valid.rs");
+    t.compile_fail("tests/ui/const_invalid_check_character.rs");
+    t.compile_fail("tests/ui/const_lowercase.rs");
+}
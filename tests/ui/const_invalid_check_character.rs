@@ -0,0 +1,7 @@
+use nhi::NHI;
+
+const CLINIC: NHI = NHI::from_str_const("ZBN77VK");
+
+fn main() {
+    let _ = CLINIC;
+}
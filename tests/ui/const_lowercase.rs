@@ -0,0 +1,7 @@
+use nhi::NHI;
+
+const CLINIC: NHI = NHI::from_str_const("jbx3656");
+
+fn main() {
+    let _ = CLINIC;
+}
@@ -0,0 +1,9 @@
+use nhi::NHI;
+
+const CLINIC: NHI = NHI::from_str_const("JBX3656");
+static REFERRER: NHI = NHI::from_str_const("ZBN77VL");
+
+fn main() {
+    assert_eq!(CLINIC.as_str(), "JBX3656");
+    assert!(REFERRER.is_test());
+}
@@ -0,0 +1,5 @@
+use nhi::nhi;
+
+fn main() {
+    let _ = nhi!("ZBN-77-VL");
+}
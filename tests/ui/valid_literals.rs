@@ -0,0 +1,8 @@
+use nhi::{nhi, NHI};
+
+fn main() {
+    let old: NHI = nhi!("ZAC5361");
+    let new: NHI = nhi!("zbn77vl");
+    assert_eq!(old.as_str(), "ZAC5361");
+    assert_eq!(new.as_str(), "ZBN77VL");
+}